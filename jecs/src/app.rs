@@ -0,0 +1,398 @@
+use crate::condition::Condition;
+use crate::data::Data;
+use crate::diagnostics::Diagnostics;
+use crate::errors::Result;
+use crate::registry::shorten_type_name;
+use crate::replay::{Command, CommandQueue, Replay};
+use crate::state::{in_state, NextState, State, StateTransitions, StateValue, Transitions};
+use crate::time::{FixedTime, Time};
+use crate::World;
+use std::any::{type_name, Any, TypeId};
+use std::fmt::{self, Debug};
+
+pub type SystemFn = Box<dyn FnMut(&mut World) -> Result<()>>;
+
+pub struct System {
+    name: String,
+    run: SystemFn,
+    conditions: Vec<Box<dyn Condition>>,
+}
+
+impl System {
+    pub fn new(name: impl Into<String>, run: impl FnMut(&mut World) -> Result<()> + 'static) -> Self {
+        Self {
+            name: name.into(),
+            run: Box::new(run),
+            conditions: vec![],
+        }
+    }
+
+    // Every condition added this way has to hold for the system to run.
+    pub fn run_if(mut self, condition: impl Condition) -> Self {
+        self.conditions.push(Box::new(condition));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Skips the system, without an error, when a condition doesn't hold.
+    pub fn run(&mut self, world: &mut World) -> Result<()> {
+        if !self.conditions.iter_mut().all(|condition| condition.evaluate(world)) {
+            return Ok(());
+        }
+
+        let run = &mut self.run;
+        world.profile(&self.name, |world| run(world))
+    }
+}
+
+impl Debug for System {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("System").field("name", &self.name).finish()
+    }
+}
+
+#[derive(Default)]
+pub struct Schedule {
+    transitions: Vec<(TypeId, Box<dyn Transitions>)>,
+    fixed_systems: Vec<System>,
+    systems: Vec<System>,
+}
+
+impl Schedule {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add_system(&mut self, system: System) {
+        self.systems.push(system);
+    }
+
+    pub fn add_fixed_system(&mut self, system: System) {
+        self.fixed_systems.push(system);
+    }
+
+    pub fn add_system_on_enter<S: StateValue>(&mut self, state: S, system: System) {
+        self.state_transitions::<S>().add_on_enter(state, system);
+    }
+
+    pub fn add_system_on_exit<S: StateValue>(&mut self, state: S, system: System) {
+        self.state_transitions::<S>().add_on_exit(state, system);
+    }
+
+    fn state_transitions<S: StateValue>(&mut self) -> &mut StateTransitions<S> {
+        let type_id = TypeId::of::<S>();
+        let position = match self.transitions.iter().position(|(id, _)| *id == type_id) {
+            Some(position) => position,
+            None => {
+                self.transitions
+                    .push((type_id, Box::new(StateTransitions::<S>::default())));
+                self.transitions.len() - 1
+            }
+        };
+
+        self.transitions[position]
+            .1
+            .as_any_mut()
+            .downcast_mut::<StateTransitions<S>>()
+            .unwrap()
+    }
+
+    pub fn system_names(&self) -> Vec<&str> {
+        self.systems.iter().map(System::name).collect()
+    }
+
+    pub fn fixed_system_names(&self) -> Vec<&str> {
+        self.fixed_systems.iter().map(System::name).collect()
+    }
+
+    // State transitions requested during the previous run are applied before
+    // any system runs, so every system in a run sees the same states. The
+    // fixed stage runs next, as many times as the frame's delta allows.
+    pub fn run(&mut self, world: &mut World) -> Result<()> {
+        for (_, transitions) in &mut self.transitions {
+            transitions.apply(world)?;
+        }
+
+        self.run_fixed(world)?;
+
+        for system in &mut self.systems {
+            system.run(world)?;
+        }
+
+        Ok(())
+    }
+
+    // Without both `Time` and `FixedTime` resources the fixed stage is skipped.
+    fn run_fixed(&mut self, world: &mut World) -> Result<()> {
+        let delta = match world.get_resource::<Time>() {
+            Some(time) => time.delta(),
+            None => return Ok(()),
+        };
+        let steps = match world.get_resource_mut::<FixedTime>() {
+            Some(fixed) => fixed.steps_due(delta),
+            None => return Ok(()),
+        };
+
+        for _ in 0..steps {
+            for system in &mut self.fixed_systems {
+                system.run(world)?;
+            }
+            if let Some(fixed) = world.get_resource_mut::<FixedTime>() {
+                fixed.finish_step();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Schedule")
+            .field("fixed_systems", &self.fixed_systems)
+            .field("systems", &self.systems)
+            .finish()
+    }
+}
+
+pub trait Plugin {
+    fn build(self, app: &mut App);
+}
+
+#[derive(Debug, Default)]
+pub struct App {
+    world: World,
+    schedule: Schedule,
+    commands: CommandQueue,
+}
+
+impl App {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add_plugin(&mut self, plugin: impl Plugin) -> &mut Self {
+        plugin.build(self);
+        self
+    }
+
+    // Systems are named after their function, which is what profiling reports.
+    pub fn add_system<F>(&mut self, system: F) -> &mut Self
+    where
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let name = shorten_type_name(type_name::<F>());
+        self.add_system_with_name(name, system)
+    }
+
+    pub fn add_system_with_name(
+        &mut self,
+        name: impl Into<String>,
+        system: impl FnMut(&mut World) -> Result<()> + 'static,
+    ) -> &mut Self {
+        self.schedule.add_system(System::new(name, system));
+        self
+    }
+
+    pub fn add_fixed_system<F>(&mut self, system: F) -> &mut Self
+    where
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let system = System::new(shorten_type_name(type_name::<F>()), system);
+        self.schedule.add_fixed_system(system);
+        self
+    }
+
+    pub fn add_state<S: StateValue>(&mut self, initial: S) -> &mut Self {
+        self.world.add_resource(State::new(initial));
+        self.world.add_resource(NextState::<S>::default());
+        self.schedule.state_transitions::<S>();
+        self
+    }
+
+    pub fn add_system_in_state<S, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        S: StateValue,
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let system = System::new(shorten_type_name(type_name::<F>()), system);
+        self.schedule.add_system(system.run_if(in_state(state)));
+        self
+    }
+
+    pub fn add_system_run_if<F>(&mut self, system: F, condition: impl Condition) -> &mut Self
+    where
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let system = System::new(shorten_type_name(type_name::<F>()), system);
+        self.schedule.add_system(system.run_if(condition));
+        self
+    }
+
+    pub fn add_boxed_system(&mut self, system: System) -> &mut Self {
+        self.schedule.add_system(system);
+        self
+    }
+
+    pub fn add_system_on_enter<S, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        S: StateValue,
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let system = System::new(shorten_type_name(type_name::<F>()), system);
+        self.schedule.add_system_on_enter(state, system);
+        self
+    }
+
+    pub fn add_system_on_exit<S, F>(&mut self, state: S, system: F) -> &mut Self
+    where
+        S: StateValue,
+        F: FnMut(&mut World) -> Result<()> + 'static,
+    {
+        let system = System::new(shorten_type_name(type_name::<F>()), system);
+        self.schedule.add_system_on_exit(state, system);
+        self
+    }
+
+    pub fn insert_resource(&mut self, resource: impl Data) -> &mut Self {
+        self.world.add_resource(resource);
+        self
+    }
+
+    pub fn insert_non_send_resource(&mut self, resource: impl Any) -> &mut Self {
+        self.world.add_non_send_resource(resource);
+        self
+    }
+
+    pub fn register_component<T: Data>(&mut self) -> &mut Self {
+        self.world.register_component::<T>();
+        self
+    }
+
+    pub fn register_command<C: Command>(&mut self) -> &mut Self {
+        self.commands.register::<C>();
+        self
+    }
+
+    // Queues the command for the start of the next update. Ignored while a
+    // replay is playing, since the replay supplies the commands then.
+    pub fn send_command<C: Command>(&mut self, command: C) -> Result<()> {
+        self.commands.send(command)
+    }
+
+    // Records every update from now on: frame times, commands and world
+    // hashes. An `Rng` resource seeded with `seed` is inserted and hashed.
+    pub fn start_recording(&mut self, seed: u64) -> &mut Self {
+        self.commands.start_recording(&mut self.world, seed);
+        self
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Replay> {
+        self.commands.stop_recording()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.commands.is_recording()
+    }
+
+    // Plays the replay back over the next updates, which fail with
+    // `ReplayDiverged` as soon as the world hash differs from the recording.
+    // The app should be set up the way the recorded one was.
+    pub fn play(&mut self, replay: Replay) -> &mut Self {
+        self.commands.play(&mut self.world, replay);
+        self
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.commands.is_playing()
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn schedule(&self) -> &Schedule {
+        &self.schedule
+    }
+
+    pub fn update(&mut self) -> Result<()> {
+        let playback_delta = self.commands.playback_delta();
+        let delta = match self.world.get_resource_mut::<Time>() {
+            Some(time) => {
+                match playback_delta {
+                    Some(delta) => time.advance(delta),
+                    None => time.update(),
+                }
+                time.raw_delta()
+            }
+            None => playback_delta.unwrap_or_default(),
+        };
+
+        self.commands.apply_tick(&mut self.world, delta)?;
+        self.schedule.run(&mut self.world)?;
+        self.world.trim_removed_components();
+        self.world.update_events();
+
+        if let Some(diagnostics) = self.world.get_resource_mut::<Diagnostics>() {
+            diagnostics.finish_tick();
+        }
+
+        self.commands.finish_tick(&self.world)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn systems_run_in_insertion_order() -> Result<()> {
+        let mut app = App::new();
+        app.insert_resource(Log(vec![]))
+            .add_system_with_name("first", |world| {
+                world.get_resource_mut::<Log>().unwrap().0.push("first");
+                Ok(())
+            })
+            .add_system_with_name("second", |world| {
+                world.get_resource_mut::<Log>().unwrap().0.push("second");
+                Ok(())
+            });
+
+        app.update()?;
+        app.update()?;
+
+        let log = app.world().get_resource::<Log>().unwrap();
+        assert_eq!(log.0, vec!["first", "second", "first", "second"]);
+        Ok(())
+    }
+
+    #[test]
+    fn systems_are_named_after_their_function() {
+        let mut app = App::new();
+        app.add_system(noop);
+        assert_eq!(app.schedule().system_names(), vec!["noop"]);
+    }
+
+    #[test]
+    fn failing_system_stops_the_update() {
+        let mut app = App::new();
+        app.insert_resource(Log(vec![]))
+            .add_system_with_name("failing", |_| Err(eyre::eyre!("boom").into()))
+            .add_system_with_name("skipped", |world| {
+                world.get_resource_mut::<Log>().unwrap().0.push("skipped");
+                Ok(())
+            });
+
+        assert!(app.update().is_err());
+        assert!(app.world().get_resource::<Log>().unwrap().0.is_empty());
+    }
+
+    fn noop(_: &mut World) -> Result<()> {
+        Ok(())
+    }
+
+    struct Log(pub Vec<&'static str>);
+}
@@ -0,0 +1,165 @@
+use crate::data::Data;
+use crate::events::Events;
+use crate::World;
+use std::any::Any;
+
+// Decides whether a system runs this time. Conditions only get shared access
+// to the world but may keep their own state, like a tick counter.
+pub trait Condition: 'static {
+    fn evaluate(&mut self, world: &World) -> bool;
+
+    fn and<C: Condition>(self, other: C) -> And<Self, C>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    fn or<C: Condition>(self, other: C) -> Or<Self, C>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+}
+
+impl<F: FnMut(&World) -> bool + 'static> Condition for F {
+    fn evaluate(&mut self, world: &World) -> bool {
+        self(world)
+    }
+}
+
+// Both `And` and `Or` short-circuit, so a stateful right-hand side is only
+// advanced when it is actually evaluated.
+#[derive(Debug, Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A: Condition, B: Condition> Condition for And<A, B> {
+    fn evaluate(&mut self, world: &World) -> bool {
+        self.0.evaluate(world) && self.1.evaluate(world)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A: Condition, B: Condition> Condition for Or<A, B> {
+    fn evaluate(&mut self, world: &World) -> bool {
+        self.0.evaluate(world) || self.1.evaluate(world)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Not<C>(C);
+
+impl<C: Condition> Condition for Not<C> {
+    fn evaluate(&mut self, world: &World) -> bool {
+        !self.0.evaluate(world)
+    }
+}
+
+pub fn not<C: Condition>(condition: C) -> Not<C> {
+    Not(condition)
+}
+
+pub fn resource_exists<T: Any>() -> impl Condition {
+    |world: &World| world.get_resource::<T>().is_some()
+}
+
+pub fn resource_matches<T: Any>(predicate: impl Fn(&T) -> bool + 'static) -> impl Condition {
+    move |world: &World| world.get_resource::<T>().is_some_and(&predicate)
+}
+
+// True on the first evaluation and then on every `n`th one after it.
+pub fn every_n_ticks(n: u64) -> impl Condition {
+    let n = n.max(1);
+    let mut ticks = 0;
+    move |_: &World| {
+        let run = ticks % n == 0;
+        ticks += 1;
+        run
+    }
+}
+
+pub fn on_event<E: Data>() -> impl Condition {
+    |world: &World| world.get_resource::<Events<E>>().is_some_and(|events| !events.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_conditions() {
+        let mut world = World::new();
+        let mut exists = resource_exists::<Clicked>();
+        let mut clicked = resource_matches(|clicked: &Clicked| clicked.0.is_some());
+        assert!(!exists.evaluate(&world));
+        assert!(!clicked.evaluate(&world));
+
+        world.add_resource(Clicked(None));
+        assert!(exists.evaluate(&world));
+        assert!(!clicked.evaluate(&world));
+
+        world.get_resource_mut::<Clicked>().unwrap().0 = Some((1.0, 2.0));
+        assert!(clicked.evaluate(&world));
+    }
+
+    #[test]
+    fn every_n_ticks_condition() {
+        let world = World::new();
+        let mut every_third = every_n_ticks(3);
+        let runs: Vec<bool> = (0..7).map(|_| every_third.evaluate(&world)).collect();
+        assert_eq!(runs, vec![true, false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn on_event_condition() {
+        let mut world = World::new();
+        let mut pending = on_event::<Bitten>();
+        assert!(!pending.evaluate(&world));
+
+        world.send_event(Bitten);
+        assert!(pending.evaluate(&world));
+
+        world.drain_events::<Bitten>();
+        assert!(!pending.evaluate(&world));
+    }
+
+    #[test]
+    fn combine_conditions() {
+        let mut world = World::new();
+        let mut both = resource_exists::<Clicked>().and(resource_exists::<Bitten>());
+        let mut either = resource_exists::<Clicked>().or(resource_exists::<Bitten>());
+        let mut neither = not(either_exists());
+
+        world.add_resource(Clicked(None));
+        assert!(!both.evaluate(&world));
+        assert!(either.evaluate(&world));
+        assert!(!neither.evaluate(&world));
+
+        world.add_resource(Bitten);
+        assert!(both.evaluate(&world));
+    }
+
+    #[test]
+    fn and_short_circuits() {
+        let world = World::new();
+        let mut gated = resource_exists::<Clicked>().and(every_n_ticks(2));
+        for _ in 0..3 {
+            assert!(!gated.evaluate(&world));
+        }
+
+        let mut world = world;
+        world.add_resource(Clicked(None));
+        assert!(gated.evaluate(&world));
+        assert!(!gated.evaluate(&world));
+    }
+
+    fn either_exists() -> impl Condition {
+        resource_exists::<Clicked>().or(resource_exists::<Bitten>())
+    }
+
+    struct Clicked(pub Option<(f32, f32)>);
+    struct Bitten;
+}
@@ -0,0 +1,15 @@
+use std::any::Any;
+
+// Anything stored in a World, components and resources alike. With the `sync`
+// feature enabled it must also be safe to send and share across threads.
+#[cfg(not(feature = "sync"))]
+pub trait Data: Any {}
+
+#[cfg(not(feature = "sync"))]
+impl<T: Any> Data for T {}
+
+#[cfg(feature = "sync")]
+pub trait Data: Any + Send + Sync {}
+
+#[cfg(feature = "sync")]
+impl<T: Any + Send + Sync> Data for T {}
@@ -0,0 +1,287 @@
+use crate::app::{App, Plugin};
+use crate::errors::Result;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const DEFAULT_WINDOW: usize = 120;
+const DEFAULT_TRACE_CAPACITY: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeStats {
+    pub min: Duration,
+    pub avg: Duration,
+    pub max: Duration,
+    pub p99: Duration,
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone)]
+struct TraceEvent {
+    name: String,
+    start: Duration,
+    duration: Duration,
+    tick: u64,
+}
+
+#[derive(Debug)]
+pub struct ScopeTimer {
+    name: String,
+    start: Instant,
+}
+
+impl ScopeTimer {
+    pub fn start(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            start: Instant::now(),
+        }
+    }
+}
+
+// Profiling is switched on by adding this as a resource. Scope timings are
+// summed over a tick, and the last `window` ticks are kept per scope. With
+// tracing on, only the newest `trace_capacity` events are kept.
+#[derive(Debug)]
+pub struct Diagnostics {
+    window: usize,
+    tick: u64,
+    epoch: Instant,
+    current_tick: HashMap<String, Duration>,
+    history: HashMap<String, VecDeque<Duration>>,
+    tracing: bool,
+    trace_capacity: usize,
+    trace: VecDeque<TraceEvent>,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self {
+            window: DEFAULT_WINDOW,
+            tick: 0,
+            epoch: Instant::now(),
+            current_tick: HashMap::new(),
+            history: HashMap::new(),
+            tracing: false,
+            trace_capacity: DEFAULT_TRACE_CAPACITY,
+            trace: VecDeque::new(),
+        }
+    }
+}
+
+impl Diagnostics {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    pub fn with_tracing(mut self) -> Self {
+        self.tracing = true;
+        self
+    }
+
+    pub fn with_trace_capacity(mut self, capacity: usize) -> Self {
+        self.trace_capacity = capacity.max(1);
+        self
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn finish(&mut self, timer: ScopeTimer) {
+        let duration = timer.start.elapsed();
+        self.record(&timer.name, timer.start, duration);
+    }
+
+    pub fn record(&mut self, name: &str, start: Instant, duration: Duration) {
+        *self.current_tick.entry(name.to_owned()).or_default() += duration;
+
+        if self.tracing {
+            if self.trace.len() == self.trace_capacity {
+                self.trace.pop_front();
+            }
+            self.trace.push_back(TraceEvent {
+                name: name.to_owned(),
+                start: start.saturating_duration_since(self.epoch),
+                duration,
+                tick: self.tick,
+            });
+        }
+    }
+
+    pub fn finish_tick(&mut self) {
+        for (name, duration) in self.current_tick.drain() {
+            let samples = self.history.entry(name).or_default();
+            if samples.len() == self.window {
+                samples.pop_front();
+            }
+            samples.push_back(duration);
+        }
+
+        self.tick += 1;
+    }
+
+    pub fn stats(&self, name: &str) -> Option<ScopeStats> {
+        let samples = self.history.get(name)?;
+        if samples.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<Duration> = samples.iter().copied().collect();
+        sorted.sort();
+        let total: Duration = sorted.iter().sum();
+        let p99_index = ((sorted.len() as f64 * 0.99).ceil() as usize).saturating_sub(1);
+
+        Some(ScopeStats {
+            min: sorted[0],
+            avg: total / sorted.len() as u32,
+            max: sorted[sorted.len() - 1],
+            p99: sorted[p99_index],
+            samples: sorted.len(),
+        })
+    }
+
+    pub fn scope_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.history.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub fn clear_trace(&mut self) {
+        self.trace.clear();
+    }
+
+    // Complete ("X") events in the Chrome trace-event format, loadable in
+    // chrome://tracing or Perfetto.
+    pub fn chrome_trace(&self) -> String {
+        let events: Vec<serde_json::Value> = self
+            .trace
+            .iter()
+            .map(|event| {
+                json!({
+                    "name": event.name,
+                    "cat": "jecs",
+                    "ph": "X",
+                    "ts": event.start.as_secs_f64() * 1_000_000.0,
+                    "dur": event.duration.as_secs_f64() * 1_000_000.0,
+                    "pid": 1,
+                    "tid": 1,
+                    "args": { "tick": event.tick },
+                })
+            })
+            .collect();
+
+        json!({ "traceEvents": events }).to_string()
+    }
+
+    pub fn write_chrome_trace(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.chrome_trace())?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DiagnosticsPlugin {
+    pub window: Option<usize>,
+    pub tracing: bool,
+    pub trace_capacity: Option<usize>,
+}
+
+impl Plugin for DiagnosticsPlugin {
+    fn build(self, app: &mut App) {
+        let mut diagnostics = Diagnostics::new();
+        if let Some(window) = self.window {
+            diagnostics = diagnostics.with_window(window);
+        }
+        if self.tracing {
+            diagnostics = diagnostics.with_tracing();
+        }
+        if let Some(capacity) = self.trace_capacity {
+            diagnostics = diagnostics.with_trace_capacity(capacity);
+        }
+
+        app.insert_resource(diagnostics);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scope_durations_are_summed_per_tick() {
+        let mut diagnostics = Diagnostics::new();
+        let now = Instant::now();
+        diagnostics.record("movement", now, Duration::from_millis(2));
+        diagnostics.record("movement", now, Duration::from_millis(3));
+        assert!(diagnostics.stats("movement").is_none());
+
+        diagnostics.finish_tick();
+        let stats = diagnostics.stats("movement").unwrap();
+        assert_eq!(stats.samples, 1);
+        assert_eq!(stats.max, Duration::from_millis(5));
+        assert_eq!(diagnostics.tick(), 1);
+    }
+
+    #[test]
+    fn rolling_statistics() {
+        let mut diagnostics = Diagnostics::new().with_window(100);
+        let now = Instant::now();
+        for millis in 1..=200 {
+            diagnostics.record("render", now, Duration::from_millis(millis));
+            diagnostics.finish_tick();
+        }
+
+        let stats = diagnostics.stats("render").unwrap();
+        assert_eq!(stats.samples, 100);
+        assert_eq!(stats.min, Duration::from_millis(101));
+        assert_eq!(stats.max, Duration::from_millis(200));
+        assert_eq!(stats.avg, Duration::from_micros(150_500));
+        assert_eq!(stats.p99, Duration::from_millis(199));
+        assert_eq!(diagnostics.scope_names(), vec!["render"]);
+    }
+
+    #[test]
+    fn chrome_trace_contains_complete_events() {
+        let mut diagnostics = Diagnostics::new().with_tracing();
+        let timer = ScopeTimer::start("collisions");
+        diagnostics.finish(timer);
+        diagnostics.finish_tick();
+        diagnostics.record("collisions", Instant::now(), Duration::from_micros(10));
+
+        let trace: serde_json::Value = serde_json::from_str(&diagnostics.chrome_trace()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["name"], "collisions");
+        assert_eq!(events[0]["ph"], "X");
+        assert_eq!(events[1]["args"]["tick"], 1);
+        assert_eq!(events[1]["dur"], 10.0);
+    }
+
+    #[test]
+    fn trace_keeps_the_newest_events() {
+        let mut diagnostics = Diagnostics::new().with_tracing().with_trace_capacity(2);
+        let now = Instant::now();
+        for micros in 1..=5 {
+            diagnostics.record("collisions", now, Duration::from_micros(micros));
+        }
+
+        let trace: serde_json::Value = serde_json::from_str(&diagnostics.chrome_trace()).unwrap();
+        let events = trace["traceEvents"].as_array().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["dur"], 4.0);
+        assert_eq!(events[1]["dur"], 5.0);
+    }
+
+    #[test]
+    fn tracing_is_off_by_default() {
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.record("collisions", Instant::now(), Duration::from_micros(10));
+        assert_eq!(diagnostics.chrome_trace(), r#"{"traceEvents":[]}"#);
+    }
+}
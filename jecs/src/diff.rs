@@ -0,0 +1,175 @@
+use crate::entities::Entity;
+use crate::errors::Result;
+use crate::registry::ComponentInfo;
+use crate::World;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+
+type HashedComponent<'a> = Option<(&'a ComponentInfo, Vec<u8>)>;
+
+// Entities are matched by id and generation, so a respawned entity shows up
+// as missing on one side and new on the other. Only hashed components are
+// compared, see `World::register_hashed_component`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorldDiff {
+    pub only_in_left: Vec<Entity>,
+    pub only_in_right: Vec<Entity>,
+    pub components: Vec<ComponentDiff>,
+}
+
+// `left` and `right` are `None` when that world's entity doesn't have the
+// component. Values are described like in dumps, falling back on `?`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentDiff {
+    pub entity: Entity,
+    pub type_name: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl WorldDiff {
+    pub fn new(left: &World, right: &World) -> Result<Self> {
+        let mut diff = Self::default();
+        let left_hashed = left.hashed_infos();
+        let right_hashed = right.hashed_infos();
+
+        for entity in left.entities.iter() {
+            if right.entities.entity(entity.id) != Some(entity) {
+                diff.only_in_left.push(entity);
+                continue;
+            }
+
+            let mut components: BTreeMap<&str, (HashedComponent, HashedComponent)> = BTreeMap::new();
+            for (info, bytes) in left.hashed_components(&left_hashed, entity.id)? {
+                components.entry(info.name()).or_default().0 = Some((info, bytes));
+            }
+            for (info, bytes) in right.hashed_components(&right_hashed, entity.id)? {
+                components.entry(info.name()).or_default().1 = Some((info, bytes));
+            }
+
+            for (left_component, right_component) in components.into_values() {
+                let left_bytes = left_component.as_ref().map(|(_, bytes)| bytes);
+                let right_bytes = right_component.as_ref().map(|(_, bytes)| bytes);
+                if left_bytes == right_bytes {
+                    continue;
+                }
+
+                let info = left_component.as_ref().or(right_component.as_ref()).unwrap().0;
+                diff.components.push(ComponentDiff {
+                    entity,
+                    type_name: info.short_name().to_owned(),
+                    left: left_component.map(|(info, _)| describe(left, info, entity)),
+                    right: right_component.map(|(info, _)| describe(right, info, entity)),
+                });
+            }
+        }
+
+        diff.only_in_right = right
+            .entities
+            .iter()
+            .filter(|entity| left.entities.entity(entity.id) != Some(*entity))
+            .collect();
+
+        Ok(diff)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.only_in_left.is_empty() && self.only_in_right.is_empty() && self.components.is_empty()
+    }
+}
+
+fn describe(world: &World, info: &ComponentInfo, entity: Entity) -> String {
+    world
+        .entities
+        .get_component(info.id(), entity.id)
+        .and_then(|component| world.registry.describe(&info.id(), &*component.borrow()))
+        .unwrap_or_else(|| "?".to_owned())
+}
+
+impl Display for WorldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "Worlds are identical");
+        }
+
+        for entity in &self.only_in_left {
+            writeln!(f, "Entity {} (generation {}) only in the left world", entity.id, entity.generation)?;
+        }
+        for entity in &self.only_in_right {
+            writeln!(f, "Entity {} (generation {}) only in the right world", entity.id, entity.generation)?;
+        }
+        for component in &self.components {
+            writeln!(f, "{}", component)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ComponentDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Entity {} {}: ", self.entity.id, self.type_name)?;
+        match (&self.left, &self.right) {
+            (Some(left), Some(right)) => write!(f, "{} != {}", left, right),
+            (Some(left), None) => write!(f, "{} only in the left world", left),
+            (None, Some(right)) => write!(f, "{} only in the right world", right),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    #[test]
+    fn identical_worlds_have_an_empty_diff() -> Result<()> {
+        let world = initialize_world()?;
+        let other = initialize_world()?;
+
+        let diff = world.diff(&other)?;
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "Worlds are identical\n");
+        assert_eq!(world.hash()?, other.hash()?);
+        Ok(())
+    }
+
+    #[test]
+    fn diff_as_text() -> Result<()> {
+        let world = initialize_world()?;
+        let mut other = initialize_world()?;
+        other.set_component(Health(90), 0)?;
+        other.delete_component_by_entity_id::<Health>(1)?;
+        other.create_entity().with_component(Health(10))?;
+
+        assert_eq!(
+            world.diff(&other)?.to_string(),
+            "Entity 2 (generation 0) only in the right world\n\
+             Entity 0 Health: Health(100) != Health(90)\n\
+             Entity 1 Health: Health(50) only in the left world\n"
+        );
+        assert_ne!(world.hash()?, other.hash()?);
+        Ok(())
+    }
+
+    fn initialize_world() -> Result<World> {
+        let mut world = World::new();
+        world.register_hashed_component::<Health>();
+        world.register_hashed_component::<Armor>();
+        world.register_debug::<Health>();
+        world.create_entity().with_component(Health(100))?;
+        world
+            .create_entity()
+            .with_component(Health(50))?
+            .with_component(Armor(2))?;
+        Ok(world)
+    }
+
+    #[derive(Debug, Serialize)]
+    struct Health(u32);
+
+    #[derive(Debug, Serialize)]
+    struct Armor(u32);
+}
@@ -0,0 +1,219 @@
+use crate::registry::shorten_type_name;
+use crate::World;
+use serde::Serialize;
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorldDump {
+    pub entities: Vec<EntityDump>,
+    pub resources: Vec<ValueDump>,
+    pub component_counts: Vec<ComponentCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EntityDump {
+    pub id: usize,
+    pub generation: u32,
+    pub name: Option<String>,
+    pub components: Vec<ValueDump>,
+}
+
+// `value` is only filled in for types registered with `Debug` or reflection.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValueDump {
+    pub type_name: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComponentCount {
+    pub type_name: String,
+    pub count: usize,
+}
+
+impl WorldDump {
+    pub fn new(world: &World) -> Self {
+        let component_ids = world.entities.component_ids();
+        let component_name = |id| {
+            world
+                .registry
+                .get(id)
+                .map(|info| info.short_name().to_owned())
+                .unwrap_or_else(|| world.entities.component_name(*id))
+        };
+
+        let entities = world
+            .entities
+            .iter()
+            .map(|entity| {
+                let components = component_ids
+                    .iter()
+                    .filter_map(|id| {
+                        let component = world.entities.get_component(*id, entity.id)?;
+                        let value = match component.try_borrow() {
+                            Ok(borrowed_component) => {
+                                world.registry.describe(id, &*borrowed_component)
+                            }
+                            Err(_) => Some("<borrowed>".to_owned()),
+                        };
+
+                        Some(ValueDump {
+                            type_name: component_name(id),
+                            value,
+                        })
+                    })
+                    .collect();
+
+                EntityDump {
+                    id: entity.id,
+                    generation: entity.generation,
+                    name: world.entities.name(entity.id),
+                    components,
+                }
+            })
+            .collect();
+
+        let mut resources: Vec<ValueDump> = world
+            .resources
+            .iter()
+            .map(|(type_id, name, resource)| ValueDump {
+                type_name: shorten_type_name(name),
+                value: world.registry.format_debug(&type_id, resource),
+            })
+            .collect();
+        resources.sort_by(|first, second| first.type_name.cmp(&second.type_name));
+
+        let component_counts = component_ids
+            .iter()
+            .map(|id| ComponentCount {
+                type_name: component_name(id),
+                count: world.entities.count(*id),
+            })
+            .collect();
+
+        Self {
+            entities,
+            resources,
+            component_counts,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl Display for WorldDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "World: {} entities, {} resources",
+            self.entities.len(),
+            self.resources.len()
+        )?;
+
+        for entity in &self.entities {
+            match &entity.name {
+                Some(name) => writeln!(f, "Entity {} {:?} (generation {})", entity.id, name, entity.generation)?,
+                None => writeln!(f, "Entity {} (generation {})", entity.id, entity.generation)?,
+            }
+            for component in &entity.components {
+                writeln!(f, "  {}", component)?;
+            }
+        }
+
+        writeln!(f, "Resources")?;
+        for resource in &self.resources {
+            writeln!(f, "  {}", resource)?;
+        }
+
+        writeln!(f, "Components")?;
+        for count in &self.component_counts {
+            writeln!(f, "  {}: {}", count.type_name, count.count)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ValueDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: {}", self.type_name, value),
+            None => write!(f, "{}", self.type_name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use eyre::Result;
+
+    #[test]
+    fn dump_world_as_text() -> Result<()> {
+        let world = initialize_world()?;
+        let text = world.dump().to_string();
+
+        assert_eq!(
+            text,
+            "World: 2 entities, 2 resources\n\
+             Entity 0 (generation 0)\n\
+             \x20 Location: Location(16.0, 64.0)\n\
+             \x20 Size\n\
+             Entity 2 (generation 0)\n\
+             \x20 Location: Location(32.0, 128.0)\n\
+             Resources\n\
+             \x20 Fps: Fps(60)\n\
+             \x20 Opaque\n\
+             Components\n\
+             \x20 Location: 2\n\
+             \x20 Size: 1\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dump_world_as_json() -> Result<()> {
+        let world = initialize_world()?;
+        let json: serde_json::Value = serde_json::from_str(&world.dump().to_json())?;
+
+        assert_eq!(json["entities"].as_array().unwrap().len(), 2);
+        assert_eq!(json["entities"][1]["id"], 2);
+        assert_eq!(json["entities"][0]["components"][0]["type_name"], "Location");
+        assert_eq!(json["entities"][0]["components"][0]["value"], "Location(16.0, 64.0)");
+        assert!(json["entities"][0]["components"][1]["value"].is_null());
+        assert_eq!(json["component_counts"][1]["count"], 1);
+        Ok(())
+    }
+
+    fn initialize_world() -> Result<World> {
+        let mut world = World::new();
+        world.register_component::<Location>();
+        world.register_component::<Size>();
+        world.register_debug::<Location>();
+        world.register_debug::<Fps>();
+        world.add_resource(Fps(60));
+        world.add_resource(Opaque);
+
+        world
+            .create_entity()
+            .with_component(Location(16.0, 64.0))?
+            .with_component(Size(10.0))?;
+        world.create_entity().with_component(Size(5.0))?;
+        world.create_entity().with_component(Location(32.0, 128.0))?;
+        world.delete_entity_by_id(1)?;
+
+        Ok(world)
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Location(pub f32, pub f32);
+    #[allow(dead_code)]
+    struct Size(pub f32);
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Fps(pub u32);
+    struct Opaque;
+}
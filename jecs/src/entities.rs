@@ -1,31 +1,178 @@
-use crate::errors::JellyEcsError;
-use eyre::Result;
-use std::any::{Any, TypeId};
-use std::cell::RefCell;
+use crate::data::Data;
+use crate::entities::disabled::Disabled;
+use crate::entities::dynamic::{DynamicComponent, DynamicLayout};
+use crate::entities::mask::ComponentMask;
+use crate::entities::name::Name;
+use crate::entities::removed::RemovalLog;
+use crate::errors::{describe_entity, JellyEcsError, Result};
+use serde::{Deserialize, Serialize};
+use std::any::{type_name, type_name_of_val, Any, TypeId};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+#[cfg(feature = "sync")]
+use atomic_refcell::AtomicRefCell;
+
+#[cfg(not(feature = "sync"))]
+use std::cell::RefCell;
+#[cfg(not(feature = "sync"))]
 use std::rc::Rc;
 
+pub mod builder;
+pub mod disabled;
+pub mod dynamic;
+pub mod map_entities;
+pub mod mask;
+pub mod name;
 pub mod query;
+pub mod removed;
 
+#[cfg(not(feature = "sync"))]
 pub type Component = Rc<RefCell<dyn Any>>;
-pub type Components = HashMap<TypeId, Vec<Option<Component>>>;
+#[cfg(feature = "sync")]
+pub type Component = Arc<AtomicRefCell<dyn Any + Send + Sync>>;
+pub type Components = HashMap<ComponentId, Vec<Option<Component>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ComponentId {
+    Type(TypeId),
+    Dynamic(usize),
+}
+
+impl ComponentId {
+    pub fn of<T: Any>() -> Self {
+        ComponentId::Type(TypeId::of::<T>())
+    }
+}
+
+impl From<TypeId> for ComponentId {
+    fn from(type_id: TypeId) -> Self {
+        ComponentId::Type(type_id)
+    }
+}
+
+#[cfg(not(feature = "sync"))]
+pub type ComponentRefMut<'a> = std::cell::RefMut<'a, dyn Any>;
+#[cfg(feature = "sync")]
+pub type ComponentRefMut<'a> = atomic_refcell::AtomicRefMut<'a, dyn Any + Send + Sync>;
+
+#[cfg(not(feature = "sync"))]
+pub(crate) fn new_component(data: impl Data) -> Component {
+    Rc::new(RefCell::new(data))
+}
+
+#[cfg(feature = "sync")]
+pub(crate) fn new_component(data: impl Data) -> Component {
+    Arc::new(AtomicRefCell::new(data))
+}
+
+type CloneFn = fn(&Component) -> Component;
+
+fn clone_component<T: Data + Clone>(component: &Component) -> Component {
+    let borrowed_component = component.borrow();
+    new_component(borrowed_component.downcast_ref::<T>().unwrap().clone())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Entity {
+    pub id: usize,
+    pub generation: u32,
+}
 
 #[derive(Debug, Default)]
 pub struct Entities {
     components: Components,
-    bit_masks: HashMap<TypeId, u32>,
-    map: Vec<u32>,
+    bit_masks: HashMap<ComponentId, ComponentMask>,
+    map: Vec<ComponentMask>,
+    generations: Vec<u32>,
     inserting_into_index: usize,
+    cloners: HashMap<ComponentId, CloneFn>,
+    dynamic_layouts: Vec<Arc<DynamicLayout>>,
+    type_names: HashMap<ComponentId, &'static str>,
+    names: HashMap<String, Vec<usize>>,
+    indexed_names: HashMap<usize, String>,
+    pub(crate) removed: RemovalLog,
 }
 
 impl Entities {
     pub fn new() -> Self { Self::default() }
 
-    pub fn register_component<T: Any>(&mut self) {
-        let type_id = TypeId::of::<T>();
-        let bit_mask = 2u32.pow(self.bit_masks.len() as u32);
-        self.components.insert(type_id, vec![]);
-        self.bit_masks.insert(type_id, bit_mask);
+    pub fn register_component<T: Data>(&mut self) {
+        self.register_component_id(ComponentId::of::<T>());
+        self.type_names.insert(ComponentId::of::<T>(), type_name::<T>());
+    }
+
+    pub fn register_cloneable_component<T: Data + Clone>(&mut self) {
+        let id = ComponentId::of::<T>();
+        if !self.bit_masks.contains_key(&id) {
+            self.register_component::<T>();
+        }
+        self.cloners.insert(id, clone_component::<T>);
+    }
+
+    pub fn register_dynamic_component(&mut self, layout: DynamicLayout) -> ComponentId {
+        let id = ComponentId::Dynamic(self.dynamic_layouts.len());
+        self.dynamic_layouts.push(Arc::new(layout));
+        self.register_component_id(id);
+        self.cloners.insert(id, clone_component::<DynamicComponent>);
+        id
+    }
+
+    pub fn dynamic_layout(&self, id: ComponentId) -> Option<&DynamicLayout> {
+        match id {
+            ComponentId::Dynamic(index) => self.dynamic_layouts.get(index).map(|layout| &**layout),
+            ComponentId::Type(_) => None,
+        }
+    }
+
+    pub fn new_dynamic_component(&self, id: ComponentId) -> Result<DynamicComponent> {
+        let layout = match id {
+            ComponentId::Dynamic(index) => self.dynamic_layouts.get(index),
+            ComponentId::Type(_) => None,
+        }
+        .ok_or_else(|| JellyEcsError::ComponentNotRegistered {
+            entity: None,
+            name: None,
+            component: format!("{:?}", id),
+        })?;
+
+        Ok(DynamicComponent::new(id, layout.clone()))
+    }
+
+    // Dynamic ids are only indexes into this world's layouts, so a component
+    // made by another world could otherwise land under an unrelated layout.
+    pub(crate) fn check_dynamic_layout(&self, id: ComponentId, data: &Component, entity_index: Option<usize>) -> Result<()> {
+        let index = match id {
+            ComponentId::Dynamic(index) => index,
+            ComponentId::Type(_) => return Ok(()),
+        };
+
+        let data = data.borrow();
+        let component = data.downcast_ref::<DynamicComponent>();
+        match (component, self.dynamic_layouts.get(index)) {
+            (Some(component), Some(layout)) if component.layout() == &**layout => Ok(()),
+            (component, _) => Err(JellyEcsError::DynamicLayoutMismatch {
+                entity: entity_index.and_then(|index| self.entity(index)),
+                name: entity_index.and_then(|index| self.name(index)),
+                component: component.map_or_else(|| format!("{:?}", id), |component| component.layout().name().to_owned()),
+            }),
+        }
+    }
+
+    fn register_component_id(&mut self, id: ComponentId) {
+        let bit_mask = ComponentMask::bit(self.bit_masks.len());
+        self.components.insert(id, vec![None; self.map.len()]);
+        self.bit_masks.insert(id, bit_mask);
+    }
+
+    pub(crate) fn component_name(&self, id: ComponentId) -> String {
+        if let Some(name) = self.type_names.get(&id) {
+            return (*name).to_owned();
+        }
+
+        self.dynamic_layout(id)
+            .map(|layout| layout.name().to_owned())
+            .unwrap_or_else(|| format!("{:?}", id))
     }
 
     pub fn create_entity(&mut self) -> &mut Self {
@@ -33,91 +180,523 @@ impl Entities {
             .map
             .iter()
             .enumerate()
-            .find(|(_index, mask)| **mask == 0)
+            .find(|(_index, mask)| mask.is_empty())
         {
             self.inserting_into_index = index;
+            self.generations[index] = self.generations[index].wrapping_add(1);
         } else {
             self.components
                 .iter_mut()
                 .for_each(|(_, components)| components.push(None));
-            self.map.push(0);
+            self.map.push(ComponentMask::default());
+            self.generations.push(0);
             self.inserting_into_index = self.map.len() - 1;
         }
 
         self
     }
 
-    pub fn with_component(&mut self, data: impl Any) -> Result<&mut Self> {
-        let type_id = data.type_id();
+    pub fn entity(&self, index: usize) -> Option<Entity> {
+        match self.map.get(index) {
+            Some(mask) if !mask.is_empty() => Some(Entity {
+                id: index,
+                generation: self.generations[index],
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entity(entity.id) == Some(entity)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.map.len()).filter_map(move |index| self.entity(index))
+    }
+
+    pub fn component_ids(&self) -> Vec<ComponentId> {
+        let mut ids: Vec<ComponentId> = self.bit_masks.keys().copied().collect();
+        ids.sort_by_key(|id| self.bit_masks[id].first_bit());
+        ids
+    }
+
+    pub fn has_component(&self, id: ComponentId, index: usize) -> bool {
+        match (self.bit_masks.get(&id), self.map.get(index)) {
+            (Some(mask), Some(map)) => map.contains(mask),
+            _ => false,
+        }
+    }
+
+    // The bit queries check to leave disabled entities out, empty until
+    // something has been disabled.
+    pub(crate) fn disabled_mask(&self) -> ComponentMask {
+        self.get_bit_mask(&ComponentId::of::<Disabled>()).cloned().unwrap_or_default()
+    }
+
+    pub fn get_component(&self, id: ComponentId, index: usize) -> Option<&Component> {
+        if !self.has_component(id, index) {
+            return None;
+        }
+
+        self.components.get(&id)?.get(index)?.as_ref()
+    }
+
+    pub fn name(&self, index: usize) -> Option<String> {
+        let component = self.get_component(ComponentId::of::<Name>(), index)?;
+        let borrowed_component = component.borrow();
+        borrowed_component
+            .downcast_ref::<Name>()
+            .map(|name| name.as_str().to_owned())
+    }
+
+    // Names aren't unique; this returns the lowest matching entity.
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.find_all_by_name(name).into_iter().next()
+    }
+
+    // Only renames through `set_component` (or any other insert) are indexed;
+    // a `Name` swapped out through a query is no longer found under either.
+    pub fn find_all_by_name(&self, name: &str) -> Vec<Entity> {
+        self.names
+            .get(name)
+            .map(|indexes| {
+                indexes
+                    .iter()
+                    .filter(|index| self.name(**index).as_deref() == Some(name))
+                    .filter_map(|index| self.entity(*index))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // How errors refer to an entity, e.g. `entity 3 "player"`.
+    pub fn describe(&self, index: usize) -> String {
+        describe_entity(&self.handle(index), &self.name(index))
+    }
+
+    // The handle of whatever lives, or last lived, at `index`.
+    fn handle(&self, index: usize) -> Entity {
+        Entity {
+            id: index,
+            generation: self.generations.get(index).copied().unwrap_or_default(),
+        }
+    }
+
+    pub(crate) fn component_not_registered(&self, component: String, index: usize) -> JellyEcsError {
+        JellyEcsError::ComponentNotRegistered {
+            entity: self.entity(index),
+            name: self.name(index),
+            component,
+        }
+    }
+
+    pub(crate) fn entity_does_not_exist(&self, index: usize) -> JellyEcsError {
+        JellyEcsError::EntityDoesNotExist {
+            entity: self.handle(index),
+            name: self.name(index),
+        }
+    }
+
+    pub(crate) fn component_not_found(&self, component: String, index: usize) -> JellyEcsError {
+        JellyEcsError::ComponentNotFound {
+            entity: self.handle(index),
+            name: self.name(index),
+            component,
+        }
+    }
+
+    pub(crate) fn component_not_cloneable(&self, component: String, index: usize) -> JellyEcsError {
+        JellyEcsError::ComponentNotCloneable {
+            entity: self.handle(index),
+            name: self.name(index),
+            component,
+        }
+    }
+
+    fn index_name(&mut self, index: usize) {
+        if let Some(name) = self.name(index) {
+            let indexes = self.names.entry(name.clone()).or_default();
+            if let Err(position) = indexes.binary_search(&index) {
+                indexes.insert(position, index);
+            }
+            self.indexed_names.insert(index, name);
+        }
+    }
+
+    // Goes by the name the entity was indexed under, which a query may have
+    // swapped out since.
+    fn unindex_name(&mut self, index: usize) {
+        let name = match self.indexed_names.remove(&index) {
+            Some(name) => name,
+            None => return,
+        };
+        if let Some(indexes) = self.names.get_mut(&name) {
+            indexes.retain(|indexed| *indexed != index);
+            if indexes.is_empty() {
+                self.names.remove(&name);
+            }
+        }
+    }
+
+    // Keeps the `Name` index in step with a component that was just inserted.
+    fn index_component(&mut self, id: ComponentId, index: usize) {
+        if id == ComponentId::of::<Name>() {
+            self.unindex_name(index);
+            self.index_name(index);
+        }
+    }
+
+    fn unindex_component(&mut self, id: ComponentId, index: usize) {
+        if id == ComponentId::of::<Name>() {
+            self.unindex_name(index);
+        }
+    }
+
+    fn unindex_entity(&mut self, index: usize) {
+        self.unindex_name(index);
+    }
+
+    fn reindex(&mut self) {
+        self.names.clear();
+        self.indexed_names.clear();
+        for index in 0..self.map.len() {
+            self.index_name(index);
+        }
+    }
+
+    pub fn count(&self, id: ComponentId) -> usize {
+        (0..self.map.len())
+            .filter(|index| self.has_component(id, *index))
+            .count()
+    }
+
+    pub fn with_component(&mut self, data: impl Data) -> Result<&mut Self> {
+        let id = ComponentId::Type(data.type_id());
+        let name = type_name_of_val(&data).to_owned();
+        self.insert_into_new_entity(id, name, new_component(data))
+    }
+
+    pub fn with_dynamic_component(&mut self, data: DynamicComponent) -> Result<&mut Self> {
+        let id = data.id();
+        let name = data.layout().name().to_owned();
+        self.insert_into_new_entity(id, name, new_component(data))
+    }
+
+    pub(crate) fn insert_into_new_entity(&mut self, id: ComponentId, name: String, data: Component) -> Result<&mut Self> {
         let index = self.inserting_into_index;
-        if let Some(components) = self.components.get_mut(&type_id) {
+        self.check_dynamic_layout(id, &data, None)?;
+        if let Some(components) = self.components.get_mut(&id) {
             let component = components
                 .get_mut(index)
                 .ok_or(JellyEcsError::CreateEntityNeverCalled)?;
-            *component = Some(Rc::new(RefCell::new(data)));
+            *component = Some(data);
 
-            let bit_mask = self.bit_masks.get(&type_id).unwrap();
-            self.map[index] |= *bit_mask;
+            let bit_mask = self.bit_masks.get(&id).unwrap();
+            self.map[index].insert(bit_mask);
         } else {
-            return Err(JellyEcsError::ComponentNotRegistered.into());
+            return Err(JellyEcsError::ComponentNotRegistered {
+                entity: None,
+                name: None,
+                component: name,
+            });
         }
 
+        self.index_component(id, index);
+
         Ok(self)
     }
 
-    pub fn get_bit_mask(&self, type_id: &TypeId) -> Option<u32> {
-        self.bit_masks.get(type_id).copied()
+    pub(crate) fn clone_components(&self, index: usize) -> Result<Vec<(ComponentId, Component)>> {
+        if self.entity(index).is_none() {
+            return Err(self.entity_does_not_exist(index));
+        }
+
+        self.component_ids()
+            .into_iter()
+            .filter(|id| self.has_component(*id, index))
+            .map(|id| {
+                let cloner = self.cloners.get(&id).ok_or_else(|| {
+                    self.component_not_cloneable(self.component_name(id), index)
+                })?;
+                Ok((id, cloner(self.get_component(id, index).unwrap())))
+            })
+            .collect()
+    }
+
+    pub(crate) fn inserting_into_index(&self) -> usize {
+        self.inserting_into_index
+    }
+
+    pub(crate) fn inserting_into_entity(&self) -> Entity {
+        Entity {
+            id: self.inserting_into_index,
+            generation: self.generations[self.inserting_into_index],
+        }
+    }
+
+    // The raw operations below back the journal. They skip the checks of the
+    // public API since they only replay changes that already succeeded once.
+    pub(crate) fn entity_components(&self, index: usize) -> Vec<(ComponentId, Component)> {
+        self.component_ids()
+            .into_iter()
+            .filter_map(|id| Some((id, self.get_component(id, index)?.clone())))
+            .collect()
+    }
+
+    pub(crate) fn take_entity(&mut self, index: usize) -> Vec<(ComponentId, Component)> {
+        let components = self.entity_components(index);
+
+        if index < self.map.len() {
+            self.log_removals(index);
+            self.unindex_entity(index);
+            self.map[index].clear();
+        }
+
+        components
+    }
+
+    pub(crate) fn restore_entity(&mut self, entity: Entity, components: Vec<(ComponentId, Component)>) {
+        while self.map.len() <= entity.id {
+            self.components
+                .iter_mut()
+                .for_each(|(_, components)| components.push(None));
+            self.map.push(ComponentMask::default());
+            self.generations.push(0);
+        }
+
+        self.generations[entity.id] = entity.generation;
+        for (id, component) in components {
+            self.put_component(id, entity.id, component);
+        }
+    }
+
+    pub(crate) fn take_component(&mut self, id: ComponentId, index: usize) -> Option<Component> {
+        let component = self.get_component(id, index)?.clone();
+        self.unindex_component(id, index);
+        self.removed.log(id, self.handle(index));
+        self.map[index].remove(&self.bit_masks[&id]);
+
+        Some(component)
+    }
+
+    pub(crate) fn put_component(&mut self, id: ComponentId, index: usize, component: Component) {
+        if let (Some(mask), Some(components)) = (self.bit_masks.get(&id), self.components.get_mut(&id)) {
+            components[index] = Some(component);
+            self.map[index].insert(mask);
+        }
+
+        self.index_component(id, index);
+    }
+
+    pub fn get_bit_mask(&self, id: &ComponentId) -> Option<&ComponentMask> {
+        self.bit_masks.get(id)
     }
 
     pub fn delete_component_by_entity_id<T: Any>(&mut self, index: usize) -> Result<()> {
-        let type_id = TypeId::of::<T>();
-        let mask = if let Some(mask) = self.bit_masks.get(&type_id) {
-            mask
+        let id = ComponentId::of::<T>();
+        if !self.bit_masks.contains_key(&id) {
+            return Err(self.component_not_registered(type_name::<T>().to_owned(), index));
+        }
+
+        self.delete_component_by_id(id, index)
+    }
+
+    pub fn delete_component_by_id(&mut self, id: ComponentId, index: usize) -> Result<()> {
+        let mask = if let Some(mask) = self.bit_masks.get(&id) {
+            mask.clone()
         } else {
-            return Err(JellyEcsError::ComponentNotRegistered.into());
+            return Err(self.component_not_registered(self.component_name(id), index));
         };
 
-        self.map[index] ^= *mask;
+        if !self.has_component(id, index) {
+            return Err(self.component_not_found(self.component_name(id), index));
+        }
+
+        self.unindex_component(id, index);
+        self.removed.log(id, self.handle(index));
+        self.map[index].remove(&mask);
 
         Ok(())
     }
 
-    pub fn add_component_by_entity_id(&mut self, data: impl Any, index: usize) -> Result<()> {
-        let mask = if let Some(mask) = self.bit_masks.get(&data.type_id()) {
+    pub fn add_component_by_entity_id(&mut self, data: impl Data, index: usize) -> Result<()> {
+        let id = ComponentId::Type(data.type_id());
+        let name = type_name_of_val(&data).to_owned();
+        self.insert_into_entity(id, name, new_component(data), index)
+    }
+
+    pub fn add_dynamic_component_by_entity_id(&mut self, data: DynamicComponent, index: usize) -> Result<()> {
+        let id = data.id();
+        let name = data.layout().name().to_owned();
+        self.insert_into_entity(id, name, new_component(data), index)
+    }
+
+    pub(crate) fn insert_into_entity(&mut self, id: ComponentId, name: String, data: Component, index: usize) -> Result<()> {
+        let mask = if let Some(mask) = self.bit_masks.get(&id) {
             mask
         } else {
-            return Err(JellyEcsError::ComponentNotRegistered.into());
+            return Err(self.component_not_registered(name, index));
         };
 
-        self.map[index] |= *mask;
+        if self.entity(index).is_none() {
+            return Err(self.entity_does_not_exist(index));
+        }
+        self.check_dynamic_layout(id, &data, Some(index))?;
 
-        let components = self.components.get_mut(&data.type_id()).unwrap();
-        components[index] = Some(Rc::new(RefCell::new(data)));
+        self.map[index].insert(mask);
+
+        let components = self.components.get_mut(&id).unwrap();
+        components[index] = Some(data);
+
+        self.index_component(id, index);
 
         Ok(())
     }
 
     pub fn delete_entity_by_id(&mut self, index: usize) -> Result<()> {
-        if let Some(map) = self.map.get_mut(index) {
-            *map = 0;
-        } else {
-            return Err(JellyEcsError::EntityDoesNotExist.into());
+        if index >= self.map.len() {
+            return Err(self.entity_does_not_exist(index));
+        }
+
+        self.log_removals(index);
+        self.unindex_entity(index);
+        self.map[index].clear();
+
+        Ok(())
+    }
+
+    // A despawn counts as losing every component.
+    fn log_removals(&mut self, index: usize) {
+        let entity = self.handle(index);
+        for (id, mask) in &self.bit_masks {
+            if self.map[index].contains(mask) {
+                self.removed.log(*id, entity);
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> Result<EntitiesSnapshot> {
+        let mut components = HashMap::new();
+        for (type_id, column) in &self.components {
+            let mask = &self.bit_masks[type_id];
+            let cloner = self.cloners.get(type_id);
+            let mut cloned_column = Vec::with_capacity(column.len());
+            for (index, component) in column.iter().enumerate() {
+                let cloned_component = match component {
+                    Some(component) if self.map[index].contains(mask) => {
+                        let cloner = cloner.ok_or_else(|| {
+                            self.component_not_cloneable(self.component_name(*type_id), index)
+                        })?;
+                        Some(cloner(component))
+                    }
+                    _ => None,
+                };
+                cloned_column.push(cloned_component);
+            }
+            components.insert(*type_id, cloned_column);
+        }
+
+        Ok(EntitiesSnapshot {
+            components,
+            bit_masks: self.bit_masks.clone(),
+            map: self.map.clone(),
+            generations: self.generations.clone(),
+            inserting_into_index: self.inserting_into_index,
+        })
+    }
+
+    // Columns are restored one registered component at a time, so components
+    // registered after the snapshot was taken keep their column, just empty.
+    // A snapshot with live components this world can't clone, e.g. one taken
+    // from another world, is rejected before anything changes.
+    pub fn restore(&mut self, snapshot: &EntitiesSnapshot) -> Result<()> {
+        for (id, column) in &snapshot.components {
+            for component in column.iter().flatten() {
+                if !self.bit_masks.contains_key(id) || !self.cloners.contains_key(id) {
+                    return Err(JellyEcsError::SnapshotMismatch {
+                        component: self.component_name(*id),
+                    });
+                }
+                self.check_dynamic_layout(*id, component, None)?;
+            }
+        }
+
+        let ids = self.component_ids();
+        let before: Vec<(Entity, Vec<ComponentId>)> = self
+            .iter()
+            .map(|entity| {
+                let ids = ids.iter().copied().filter(|id| self.has_component(*id, entity.id));
+                (entity, ids.collect())
+            })
+            .collect();
+
+        // The snapshot's masks may differ from this world's, so the map is
+        // rebuilt from the components each entity had.
+        let len = snapshot.map.len();
+        let mut map = vec![ComponentMask::default(); len];
+        let cloners = &self.cloners;
+        for (id, column) in self.components.iter_mut() {
+            let mask = &self.bit_masks[id];
+            *column = match (snapshot.components.get(id), snapshot.bit_masks.get(id)) {
+                (Some(snapshot_column), Some(snapshot_mask)) => {
+                    for (index, snapshot_map) in snapshot.map.iter().enumerate() {
+                        if snapshot_map.contains(snapshot_mask) && snapshot_column[index].is_some() {
+                            map[index].insert(mask);
+                        }
+                    }
+                    snapshot_column
+                        .iter()
+                        .map(|component| component.as_ref().map(|component| cloners[id](component)))
+                        .collect()
+                }
+                _ => vec![None; len],
+            };
+        }
+        self.map = map;
+        self.generations = snapshot.generations.clone();
+        self.inserting_into_index = snapshot.inserting_into_index;
+        self.reindex();
+
+        // Whatever the snapshot doesn't have counts as removed.
+        for (entity, ids) in before {
+            let alive = self.is_alive(entity);
+            for id in ids {
+                if !alive || !self.has_component(id, entity.id) {
+                    self.removed.log(id, entity);
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+#[derive(Debug)]
+pub struct EntitiesSnapshot {
+    components: Components,
+    bit_masks: HashMap<ComponentId, ComponentMask>,
+    map: Vec<ComponentMask>,
+    generations: Vec<u32>,
+    inserting_into_index: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::any::TypeId;
+
+    fn mask(bits: &[usize]) -> ComponentMask {
+        let mut mask = ComponentMask::default();
+        for bit in bits {
+            mask.insert(&ComponentMask::bit(*bit));
+        }
+        mask
+    }
 
     #[test]
     fn register_entity() {
         let mut entities = Entities::new();
         entities.register_component::<Health>();
-        let health_components = entities.components.get(&TypeId::of::<Health>()).unwrap();
+        let health_components = entities.components.get(&ComponentId::of::<Health>()).unwrap();
         assert_eq!(health_components.len(), 0);
     }
 
@@ -125,12 +704,12 @@ mod tests {
     fn bitmask_updated_when_registering_entities() {
         let mut entities = Entities::new();
         entities.register_component::<Health>();
-        let mask = entities.bit_masks.get(&TypeId::of::<Health>()).unwrap();
-        assert_eq!(*mask, 1);
+        let mask = entities.bit_masks.get(&ComponentId::of::<Health>()).unwrap();
+        assert_eq!(*mask, self::mask(&[0]));
 
         entities.register_component::<Speed>();
-        let mask = entities.bit_masks.get(&TypeId::of::<Speed>()).unwrap();
-        assert_eq!(*mask, 2);
+        let mask = entities.bit_masks.get(&ComponentId::of::<Speed>()).unwrap();
+        assert_eq!(*mask, self::mask(&[1]));
     }
 
     #[test]
@@ -140,10 +719,10 @@ mod tests {
         entities.register_component::<Speed>();
         entities.create_entity();
 
-        let health = entities.components.get(&TypeId::of::<Health>()).unwrap();
+        let health = entities.components.get(&ComponentId::of::<Health>()).unwrap();
         assert_eq!(health.len(), 1);
         assert!(health[0].is_none());
-        let speed = entities.components.get(&TypeId::of::<Speed>()).unwrap();
+        let speed = entities.components.get(&ComponentId::of::<Speed>()).unwrap();
         assert_eq!(speed.len(), 1);
         assert!(speed[0].is_none());
     }
@@ -158,7 +737,7 @@ mod tests {
             .with_component(Health(100))?
             .with_component(Speed(16.0))?;
 
-        let first_health = &entities.components.get(&TypeId::of::<Health>()).unwrap()[0];
+        let first_health = &entities.components.get(&ComponentId::of::<Health>()).unwrap()[0];
         let wrapped_health = first_health.as_ref().unwrap();
         let borrowed_health = wrapped_health.borrow();
         let health = borrowed_health.downcast_ref::<Health>().unwrap();
@@ -177,12 +756,10 @@ mod tests {
             .with_component(Health(100))?
             .with_component(Speed(16.0))?;
 
-        let entity_map = entities.map[0];
-        assert_eq!(entity_map, 3);
+        assert_eq!(entities.map[0], mask(&[0, 1]));
 
         entities.create_entity().with_component(Speed(20.0))?;
-        let entity_map = entities.map[1];
-        assert_eq!(entity_map, 2);
+        assert_eq!(entities.map[1], mask(&[1]));
 
         Ok(())
     }
@@ -199,7 +776,7 @@ mod tests {
 
         entities.delete_component_by_entity_id::<Health>(0)?;
 
-        assert_eq!(entities.map[0], 2);
+        assert_eq!(entities.map[0], mask(&[1]));
         Ok(())
     }
 
@@ -213,9 +790,9 @@ mod tests {
 
         entities.add_component_by_entity_id(Speed(16.0), 0)?;
 
-        assert_eq!(entities.map[0], 3);
+        assert_eq!(entities.map[0], mask(&[0, 1]));
 
-        let wrapped_speeds = entities.components.get(&TypeId::of::<Speed>()).unwrap();
+        let wrapped_speeds = entities.components.get(&ComponentId::of::<Speed>()).unwrap();
         let wrapped_speed = wrapped_speeds[0].as_ref().unwrap();
         let borrowed_speed = wrapped_speed.borrow();
         let speed = borrowed_speed.downcast_ref::<Speed>().unwrap();
@@ -231,7 +808,7 @@ mod tests {
         entities.register_component::<Health>();
         entities.create_entity().with_component(Health(100))?;
         entities.delete_entity_by_id(0)?;
-        assert_eq!(entities.map[0], 0);
+        assert!(entities.map[0].is_empty());
         Ok(())
     }
 
@@ -244,9 +821,9 @@ mod tests {
         entities.delete_entity_by_id(0)?;
         entities.create_entity().with_component(Health(300))?;
 
-        assert_eq!(entities.map[0], 1);
+        assert_eq!(entities.map[0], mask(&[0]));
 
-        let borrowed_health = entities.components.get(&TypeId::of::<Health>()).unwrap()[0]
+        let borrowed_health = entities.components.get(&ComponentId::of::<Health>()).unwrap()[0]
             .as_ref()
             .unwrap()
             .borrow();
@@ -257,6 +834,116 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn reused_entity_slots_get_a_new_generation() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        let first = entities.entity(0).unwrap();
+        assert_eq!(first.generation, 0);
+
+        entities.delete_entity_by_id(0)?;
+        assert!(!entities.is_alive(first));
+        assert!(entities.entity(0).is_none());
+
+        entities.create_entity().with_component(Health(200))?;
+        let second = entities.entity(0).unwrap();
+        assert_eq!(second.generation, 1);
+        assert!(!entities.is_alive(first));
+        assert!(entities.is_alive(second));
+        Ok(())
+    }
+
+    #[test]
+    fn registering_a_component_after_creating_entities() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Health(100))?;
+        entities.register_component::<Speed>();
+
+        entities.add_component_by_entity_id(Speed(1.0), 0)?;
+        assert_eq!(entities.map[0], mask(&[0, 1]));
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_and_restore() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_cloneable_component::<Score>();
+        entities.create_entity().with_component(Score(1))?;
+        entities.create_entity().with_component(Score(2))?;
+        entities.delete_entity_by_id(0)?;
+        let snapshot = entities.snapshot()?;
+
+        entities.create_entity().with_component(Score(3))?;
+        entities.create_entity().with_component(Score(4))?;
+        entities.restore(&snapshot)?;
+
+        assert_eq!(entities.map, vec![mask(&[]), mask(&[0])]);
+        assert_eq!(entities.generations, vec![0, 0]);
+        let column = entities.components.get(&ComponentId::of::<Score>()).unwrap();
+        assert!(column[0].is_none());
+        let borrowed_score = column[1].as_ref().unwrap().borrow();
+        assert_eq!(borrowed_score.downcast_ref::<Score>().unwrap().0, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_fails_on_live_components_that_are_not_cloneable() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_cloneable_component::<Score>();
+        entities.register_component::<Health>();
+        entities.create_entity().with_component(Score(1))?;
+        assert!(entities.snapshot().is_ok());
+
+        entities.add_component_by_entity_id(Health(100), 0)?;
+        assert!(entities.snapshot().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn inspect_entity_components() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_component::<Health>();
+        entities.register_component::<Speed>();
+        entities.create_entity().with_component(Health(100))?;
+        entities
+            .create_entity()
+            .with_component(Health(50))?
+            .with_component(Speed(1.0))?;
+        entities.delete_entity_by_id(0)?;
+
+        let alive: Vec<usize> = entities.iter().map(|entity| entity.id).collect();
+        assert_eq!(alive, vec![1]);
+        assert_eq!(entities.component_ids(), vec![ComponentId::of::<Health>(), ComponentId::of::<Speed>()]);
+        assert_eq!(entities.count(ComponentId::of::<Health>()), 1);
+        assert!(entities.get_component(ComponentId::of::<Health>(), 0).is_none());
+
+        let health = entities.get_component(ComponentId::of::<Health>(), 1).unwrap();
+        assert_eq!(health.borrow().downcast_ref::<Health>().unwrap().0, 50);
+        Ok(())
+    }
+
+    #[test]
+    fn name_index_follows_name_changes() -> Result<()> {
+        let mut entities = Entities::new();
+        entities.register_component::<Name>();
+        entities.create_entity().with_component(Name::new("player"))?;
+        entities.create_entity().with_component(Name::new("zombie"))?;
+        assert_eq!(entities.find_by_name("player").map(|entity| entity.id), Some(0));
+
+        entities.add_component_by_entity_id(Name::new("survivor"), 0)?;
+        assert!(entities.find_by_name("player").is_none());
+        assert_eq!(entities.find_by_name("survivor").map(|entity| entity.id), Some(0));
+
+        entities.delete_component_by_entity_id::<Name>(0)?;
+        entities.delete_entity_by_id(1)?;
+        assert!(entities.names.is_empty() && entities.indexed_names.is_empty());
+        Ok(())
+    }
+
     struct Health(pub u32);
     struct Speed(pub f32);
+    #[derive(Clone)]
+    struct Score(pub u32);
 }
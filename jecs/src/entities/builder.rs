@@ -0,0 +1,50 @@
+use crate::data::Data;
+use crate::entities::dynamic::DynamicComponent;
+use crate::entities::{new_component, Component, ComponentId, Entity};
+use crate::errors::Result;
+use crate::World;
+use std::any::type_name_of_val;
+
+// Returned by `World::create_entity`. Components go through the `World`, so
+// they are journaled and rolled back like every other insert, and the spawn
+// is a single undo step together with them.
+#[derive(Debug)]
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub(crate) fn new(world: &'a mut World) -> Self {
+        Self { world }
+    }
+
+    pub fn with_component(&mut self, data: impl Data) -> Result<&mut Self> {
+        let id = ComponentId::Type(data.type_id());
+        let name = type_name_of_val(&data).to_owned();
+        self.insert(id, name, new_component(data))
+    }
+
+    pub fn with_dynamic_component(&mut self, data: DynamicComponent) -> Result<&mut Self> {
+        let id = data.id();
+        let name = data.layout().name().to_owned();
+        self.insert(id, name, new_component(data))
+    }
+
+    // The entity being built. It only counts as alive once it has a component.
+    pub fn entity(&self) -> Entity {
+        self.world.entities.inserting_into_entity()
+    }
+
+    pub(crate) fn insert(&mut self, id: ComponentId, name: String, component: Component) -> Result<&mut Self> {
+        self.world.entities.insert_into_new_entity(id, name, component)?;
+        let index = self.world.entities.inserting_into_index();
+        self.world.record_insert(id, index, None);
+        Ok(self)
+    }
+}
+
+impl Drop for EntityBuilder<'_> {
+    fn drop(&mut self) {
+        self.world.commit_transaction();
+    }
+}
@@ -0,0 +1,5 @@
+// Marks an entity as taken out of the simulation by `World::disable`. Queries
+// skip disabled entities unless they call `Query::include_disabled` or ask for
+// `Disabled` themselves; every other component stays where it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Disabled;
@@ -0,0 +1,210 @@
+use crate::entities::ComponentId;
+use crate::errors::{JellyEcsError, Result};
+use crate::registry::{Reflect, ReflectedField};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    F32,
+    I64,
+    Bool,
+    String,
+}
+
+impl FieldType {
+    pub fn default_value(&self) -> Value {
+        match self {
+            FieldType::F32 => Value::F32(0.0),
+            FieldType::I64 => Value::I64(0),
+            FieldType::Bool => Value::Bool(false),
+            FieldType::String => Value::String(String::new()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    F32(f32),
+    I64(i64),
+    Bool(bool),
+    String(String),
+}
+
+impl Value {
+    pub fn field_type(&self) -> FieldType {
+        match self {
+            Value::F32(_) => FieldType::F32,
+            Value::I64(_) => FieldType::I64,
+            Value::Bool(_) => FieldType::Bool,
+            Value::String(_) => FieldType::String,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::F32(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicLayout {
+    name: String,
+    fields: Vec<(String, FieldType)>,
+}
+
+impl DynamicLayout {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            fields: vec![],
+        }
+    }
+
+    pub fn with_field(mut self, name: impl Into<String>, field_type: FieldType) -> Self {
+        self.fields.push((name.into(), field_type));
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn fields(&self) -> &[(String, FieldType)] {
+        &self.fields
+    }
+
+    fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|(field, _)| field == name)
+    }
+}
+
+// A component whose shape is only known at runtime. It is stored in the same
+// columns as static components, under the `ComponentId` of its layout.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DynamicComponent {
+    id: ComponentId,
+    layout: Arc<DynamicLayout>,
+    values: Vec<Value>,
+}
+
+impl DynamicComponent {
+    pub(crate) fn new(id: ComponentId, layout: Arc<DynamicLayout>) -> Self {
+        let values = layout
+            .fields
+            .iter()
+            .map(|(_, field_type)| field_type.default_value())
+            .collect();
+
+        Self { id, layout, values }
+    }
+
+    pub fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    pub fn layout(&self) -> &DynamicLayout {
+        &self.layout
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Value> {
+        let index = self.layout.field_index(field)?;
+        self.values.get(index)
+    }
+
+    pub fn set(&mut self, field: &str, value: Value) -> Result<()> {
+        let index = self.layout.field_index(field).ok_or_else(|| JellyEcsError::FieldDoesNotExist {
+            component: self.layout.name.clone(),
+            field: field.to_owned(),
+        })?;
+        let expected = self.layout.fields[index].1;
+        if value.field_type() != expected {
+            return Err(JellyEcsError::FieldTypeMismatch {
+                component: self.layout.name.clone(),
+                field: field.to_owned(),
+                expected,
+                found: value.field_type(),
+            });
+        }
+
+        self.values[index] = value;
+        Ok(())
+    }
+
+    pub fn with(mut self, field: &str, value: Value) -> Result<Self> {
+        self.set(field, value)?;
+        Ok(self)
+    }
+}
+
+impl Reflect for DynamicComponent {
+    fn fields(&self) -> Vec<ReflectedField> {
+        self.layout
+            .fields
+            .iter()
+            .zip(&self.values)
+            .map(|((name, _), value)| (name.clone(), format!("{:?}", value)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_dynamic_component_has_default_values() {
+        let component = infected();
+        assert_eq!(component.get("incubation"), Some(&Value::F32(0.0)));
+        assert_eq!(component.get("strain"), Some(&Value::String(String::new())));
+        assert!(component.get("missing").is_none());
+    }
+
+    #[test]
+    fn set_dynamic_field() -> Result<()> {
+        let component = infected()
+            .with("incubation", Value::F32(5.0))?
+            .with("strain", Value::String("t-virus".to_owned()))?;
+
+        assert_eq!(component.get("incubation").and_then(Value::as_f32), Some(5.0));
+        assert_eq!(component.get("strain").and_then(Value::as_str), Some("t-virus"));
+        Ok(())
+    }
+
+    #[test]
+    fn set_dynamic_field_checks_the_layout() {
+        let mut component = infected();
+        assert!(component.set("incubation", Value::Bool(true)).is_err());
+        assert!(component.set("missing", Value::I64(1)).is_err());
+        assert_eq!(component.get("incubation"), Some(&Value::F32(0.0)));
+    }
+
+    fn infected() -> DynamicComponent {
+        let layout = DynamicLayout::new("Infected")
+            .with_field("incubation", FieldType::F32)
+            .with_field("strain", FieldType::String);
+        DynamicComponent::new(ComponentId::Dynamic(0), Arc::new(layout))
+    }
+}
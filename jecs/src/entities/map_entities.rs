@@ -0,0 +1,86 @@
+use crate::entities::Entity;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+// Where each copied entity ended up, from the source world's handle to the
+// target world's.
+#[derive(Debug, Clone, Default)]
+pub struct EntityMap {
+    entities: HashMap<Entity, Entity>,
+    // Handles `map` was asked for but doesn't know, so copies between worlds
+    // can refuse references that would dangle.
+    unmapped: RefCell<Vec<Entity>>,
+}
+
+impl EntityMap {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn insert(&mut self, source: Entity, target: Entity) {
+        self.entities.insert(source, target);
+    }
+
+    pub fn remove(&mut self, source: Entity) -> Option<Entity> {
+        self.entities.remove(&source)
+    }
+
+    pub fn get(&self, source: Entity) -> Option<Entity> {
+        self.entities.get(&source).copied()
+    }
+
+    // Entities that weren't copied along are left pointing where they did.
+    pub fn map(&self, source: Entity) -> Entity {
+        self.get(source).unwrap_or_else(|| {
+            self.unmapped.borrow_mut().push(source);
+            source
+        })
+    }
+
+    pub(crate) fn take_unmapped(&self) -> Vec<Entity> {
+        self.unmapped.take()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.entities.iter().map(|(source, target)| (*source, *target))
+    }
+}
+
+impl PartialEq for EntityMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.entities == other.entities
+    }
+}
+
+impl Eq for EntityMap {}
+
+// Implemented by components that hold entity handles, so copies made by
+// `World::clone_entity` and friends point at the copied entities.
+pub trait MapEntities {
+    fn map_entities(&mut self, map: &EntityMap);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unmapped_entities_map_to_themselves() {
+        let copied = Entity { id: 0, generation: 0 };
+        let other = Entity { id: 1, generation: 3 };
+        let mut map = EntityMap::new();
+        map.insert(copied, Entity { id: 5, generation: 1 });
+
+        assert_eq!(map.map(copied), Entity { id: 5, generation: 1 });
+        assert_eq!(map.map(other), other);
+        assert!(map.get(other).is_none());
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.take_unmapped(), vec![other]);
+    }
+}
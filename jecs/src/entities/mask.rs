@@ -0,0 +1,107 @@
+// One bit per registered component. The first 64 components fit inline so
+// the common case never allocates; anything registered past that spills into
+// `extra`, which is kept free of trailing zero words so equal masks compare
+// equal.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ComponentMask {
+    bits: u64,
+    extra: Vec<u64>,
+}
+
+impl ComponentMask {
+    pub(crate) fn bit(index: usize) -> Self {
+        let mut mask = Self::default();
+        if index < 64 {
+            mask.bits = 1 << index;
+        } else {
+            let word = index / 64 - 1;
+            mask.extra = vec![0; word + 1];
+            mask.extra[word] = 1 << (index % 64);
+        }
+        mask
+    }
+
+    // The lowest bit set, which is the registration order of a single
+    // component's mask.
+    pub(crate) fn first_bit(&self) -> Option<usize> {
+        if self.bits != 0 {
+            return Some(self.bits.trailing_zeros() as usize);
+        }
+
+        self.extra
+            .iter()
+            .position(|word| *word != 0)
+            .map(|word| (word + 1) * 64 + self.extra[word].trailing_zeros() as usize)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0 && self.extra.is_empty()
+    }
+
+    pub fn contains(&self, other: &Self) -> bool {
+        self.bits & other.bits == other.bits
+            && other
+                .extra
+                .iter()
+                .enumerate()
+                .all(|(word, bits)| self.extra.get(word).copied().unwrap_or(0) & bits == *bits)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.bits & other.bits != 0
+            || self
+                .extra
+                .iter()
+                .zip(&other.extra)
+                .any(|(bits, other_bits)| bits & other_bits != 0)
+    }
+
+    pub(crate) fn insert(&mut self, other: &Self) {
+        self.bits |= other.bits;
+        if self.extra.len() < other.extra.len() {
+            self.extra.resize(other.extra.len(), 0);
+        }
+        for (bits, other_bits) in self.extra.iter_mut().zip(&other.extra) {
+            *bits |= other_bits;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, other: &Self) {
+        self.bits &= !other.bits;
+        for (bits, other_bits) in self.extra.iter_mut().zip(&other.extra) {
+            *bits &= !other_bits;
+        }
+        while self.extra.last() == Some(&0) {
+            self.extra.pop();
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.bits = 0;
+        self.extra.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_past_64_components_spill_over() {
+        let low = ComponentMask::bit(3);
+        let high = ComponentMask::bit(130);
+        assert_eq!(high.first_bit(), Some(130));
+
+        let mut mask = ComponentMask::default();
+        mask.insert(&low);
+        mask.insert(&high);
+        assert!(mask.contains(&low) && mask.contains(&high));
+        assert!(!mask.contains(&ComponentMask::bit(129)));
+        assert!(mask.intersects(&high));
+
+        mask.remove(&high);
+        assert_eq!(mask, low);
+        mask.remove(&low);
+        assert!(mask.is_empty());
+    }
+}
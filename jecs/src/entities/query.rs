@@ -1,74 +1,271 @@
 use super::Entities;
-use crate::entities::Component;
-use crate::errors::JellyEcsError;
-use eyre::Result;
-use std::any::{Any, TypeId};
+use crate::entities::query::fetch::Fetch;
+use crate::entities::mask::ComponentMask;
+use crate::entities::{Component, ComponentId, ComponentRefMut};
+use crate::errors::{JellyEcsError, Result};
+use std::any::{type_name, Any};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+pub mod fetch;
+
+#[cfg(feature = "parallel")]
+const DEFAULT_BATCH_SIZE: usize = 64;
 
 pub type QueryIndexes = Vec<usize>;
 pub type QueryComponents = Vec<Vec<Component>>;
+pub type QueryEntity = (usize, Vec<Component>);
 
 #[derive(Debug)]
 pub struct Query<'a> {
-    map: u32,
+    map: ComponentMask,
     entities: &'a Entities,
-    type_ids: Vec<TypeId>,
+    component_ids: Vec<ComponentId>,
+    include_disabled: bool,
+    #[cfg(feature = "parallel")]
+    batch_size: usize,
 }
 
 impl<'a> Query<'a> {
     pub fn new(entities: &'a Entities) -> Self {
         Self {
             entities,
-            map: 0,
-            type_ids: vec![],
+            map: ComponentMask::default(),
+            component_ids: vec![],
+            include_disabled: false,
+            #[cfg(feature = "parallel")]
+            batch_size: DEFAULT_BATCH_SIZE,
         }
     }
 
+    #[cfg(feature = "parallel")]
+    pub fn with_batch_size(&mut self, batch_size: usize) -> &mut Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    // Also matches entities taken out with `World::disable`.
+    pub fn include_disabled(&mut self) -> &mut Self {
+        self.include_disabled = true;
+        self
+    }
+
     pub fn with_component<T: Any>(&mut self) -> Result<&mut Self> {
-        let type_id = TypeId::of::<T>();
-        if let Some(bit_mask) = self.entities.get_bit_mask(&type_id) {
-            self.map |= bit_mask;
-            self.type_ids.push(type_id);
+        let id = ComponentId::of::<T>();
+        if let Some(bit_mask) = self.entities.get_bit_mask(&id) {
+            self.map.insert(bit_mask);
+            self.component_ids.push(id);
         } else {
-            return Err(JellyEcsError::ComponentNotRegistered.into());
+            return Err(JellyEcsError::ComponentNotRegistered {
+                entity: None,
+                name: None,
+                component: type_name::<T>().to_owned(),
+            });
+        }
+
+        Ok(self)
+    }
+
+    pub fn with_component_id(&mut self, id: ComponentId) -> Result<&mut Self> {
+        if let Some(bit_mask) = self.entities.get_bit_mask(&id) {
+            self.map.insert(bit_mask);
+            self.component_ids.push(id);
+        } else {
+            return Err(JellyEcsError::ComponentNotRegistered {
+                entity: None,
+                name: None,
+                component: self.entities.component_name(id),
+            });
         }
 
         Ok(self)
     }
 
     pub fn run(&self) -> (QueryIndexes, QueryComponents) {
-        let indexes: Vec<usize> = self
-            .entities
+        let indexes = self.matching_indexes(&self.map);
+
+        let mut result = vec![];
+
+        for id in &self.component_ids {
+            let entity_components = self.entities.components.get(id).unwrap();
+            let mut components_to_keep = vec![];
+            for index in &indexes {
+                components_to_keep.push(entity_components[*index].as_ref().unwrap().clone());
+            }
+
+            result.push(components_to_keep);
+        }
+
+        (indexes, result)
+    }
+
+    pub fn iter_combinations<const K: usize>(&self) -> QueryCombinations<K> {
+        let (indexes, components) = self.run();
+        QueryCombinations::new(indexes, components)
+    }
+
+    pub fn for_each<Q, F>(&self, mut f: F) -> Result<()>
+    where
+        Q: Fetch,
+        F: FnMut(usize, Q::Item<'_>),
+    {
+        let (indexes, columns) = self.fetch_columns::<Q>()?;
+        for index in indexes {
+            let mut borrows = borrow_row(&columns, index);
+            f(index, Q::fetch(&mut borrows));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "parallel")]
+    pub fn par_for_each<Q, F>(&self, f: F) -> Result<()>
+    where
+        Q: Fetch,
+        F: Fn(usize, Q::Item<'_>) + Send + Sync,
+    {
+        let (indexes, columns) = self.fetch_columns::<Q>()?;
+        indexes.par_chunks(self.batch_size).for_each(|batch| {
+            for &index in batch {
+                let mut borrows = borrow_row(&columns, index);
+                f(index, Q::fetch(&mut borrows));
+            }
+        });
+
+        Ok(())
+    }
+
+    // Asking for `Disabled` is as good as opting in to disabled entities.
+    fn matching_indexes(&self, map: &ComponentMask) -> QueryIndexes {
+        let mut disabled = ComponentMask::default();
+        if !self.include_disabled && !map.contains(&self.entities.disabled_mask()) {
+            disabled = self.entities.disabled_mask();
+        }
+
+        self.entities
             .map
             .iter()
             .enumerate()
             .filter_map(|(index, entity_map)| {
-                if entity_map & self.map == self.map {
+                if entity_map.contains(map) && !entity_map.intersects(&disabled) {
                     Some(index)
                 } else {
                     None
                 }
             })
+            .collect()
+    }
+
+    // Every fetched column is borrowed mutably per row, so each type may only
+    // appear once.
+    fn fetch_columns<Q: Fetch>(&self) -> Result<(QueryIndexes, Vec<&'a Vec<Option<Component>>>)> {
+        let mut map = self.map.clone();
+        let mut columns = vec![];
+        let type_ids = Q::type_ids();
+        for (position, (type_id, name)) in type_ids.iter().zip(Q::type_names()).enumerate() {
+            if type_ids[..position].contains(type_id) {
+                return Err(JellyEcsError::DuplicateQueryComponent {
+                    component: name.to_owned(),
+                });
+            }
+            let id = ComponentId::Type(*type_id);
+            let bit_mask = self
+                .entities
+                .get_bit_mask(&id)
+                .ok_or_else(|| JellyEcsError::ComponentNotRegistered {
+                    entity: None,
+                    name: None,
+                    component: name.to_owned(),
+                })?;
+            map.insert(bit_mask);
+            columns.push(self.entities.components.get(&id).unwrap());
+        }
+
+        Ok((self.matching_indexes(&map), columns))
+    }
+}
+
+fn borrow_row<'c>(columns: &[&'c Vec<Option<Component>>], index: usize) -> Vec<ComponentRefMut<'c>> {
+    columns
+        .iter()
+        .map(|column| column[index].as_ref().unwrap().borrow_mut())
+        .collect()
+}
+
+// Every item holds K distinct entities, so each of their components can be
+// borrowed mutably at the same time without tripping the RefCell.
+#[derive(Debug)]
+pub struct QueryCombinations<const K: usize> {
+    indexes: QueryIndexes,
+    components: QueryComponents,
+    cursor: [usize; K],
+    done: bool,
+}
+
+impl<const K: usize> QueryCombinations<K> {
+    fn new(indexes: QueryIndexes, components: QueryComponents) -> Self {
+        let mut cursor = [0; K];
+        for (position, value) in cursor.iter_mut().enumerate() {
+            *value = position;
+        }
+
+        Self {
+            done: K == 0 || K > indexes.len(),
+            indexes,
+            components,
+            cursor,
+        }
+    }
+
+    fn entity_at(&self, position: usize) -> QueryEntity {
+        let components = self
+            .components
+            .iter()
+            .map(|column| column[position].clone())
             .collect();
 
-        let mut result = vec![];
+        (self.indexes[position], components)
+    }
 
-        for type_id in &self.type_ids {
-            let entity_components = self.entities.components.get(type_id).unwrap();
-            let mut components_to_keep = vec![];
-            for index in &indexes {
-                components_to_keep.push(entity_components[*index].as_ref().unwrap().clone());
+    fn advance(&mut self) {
+        let len = self.indexes.len();
+        let mut slot = K;
+        while slot > 0 {
+            slot -= 1;
+            if self.cursor[slot] < len - K + slot {
+                self.cursor[slot] += 1;
+                for next in slot + 1..K {
+                    self.cursor[next] = self.cursor[next - 1] + 1;
+                }
+                return;
             }
+        }
 
-            result.push(components_to_keep);
+        self.done = true;
+    }
+}
+
+impl<const K: usize> Iterator for QueryCombinations<K> {
+    type Item = [QueryEntity; K];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
         }
 
-        (indexes, result)
+        let cursor = self.cursor;
+        let item = cursor.map(|position| self.entity_at(position));
+        self.advance();
+
+        Some(item)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::entities::disabled::Disabled;
 
     #[test]
     fn query_mask_updating_with_component() -> Result<()> {
@@ -79,9 +276,11 @@ mod test {
         let mut query = Query::new(&entities);
         query.with_component::<u32>()?.with_component::<f32>()?;
 
-        assert_eq!(query.map, 3);
-        assert_eq!(TypeId::of::<u32>(), query.type_ids[0]);
-        assert_eq!(TypeId::of::<f32>(), query.type_ids[1]);
+        let mut map = ComponentMask::bit(0);
+        map.insert(&ComponentMask::bit(1));
+        assert_eq!(query.map, map);
+        assert_eq!(ComponentId::of::<u32>(), query.component_ids[0]);
+        assert_eq!(ComponentId::of::<f32>(), query.component_ids[1]);
         Ok(())
     }
 
@@ -136,4 +335,111 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn iter_combinations_yields_each_pair_once() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2.0_f32)?;
+        entities.create_entity().with_component(3_u32)?;
+        entities.create_entity().with_component(4_u32)?;
+
+        let mut query = Query::new(&entities);
+        let pairs: Vec<(usize, usize)> = query
+            .with_component::<u32>()?
+            .iter_combinations::<2>()
+            .map(|[(first, _), (second, _)]| (first, second))
+            .collect();
+
+        assert_eq!(pairs, vec![(0, 2), (0, 3), (2, 3)]);
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::float_cmp)]
+    fn for_each_hands_out_typed_components() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<u32>();
+        entities.register_component::<f32>();
+
+        entities
+            .create_entity()
+            .with_component(10_u32)?
+            .with_component(16.0_f32)?;
+        entities.create_entity().with_component(20_u32)?;
+        entities
+            .create_entity()
+            .with_component(30_u32)?
+            .with_component(64.0_f32)?;
+
+        let mut visited = vec![];
+        Query::new(&entities).for_each::<(u32, f32), _>(|index, (integer, float)| {
+            *float += *integer as f32;
+            visited.push(index);
+        })?;
+        assert_eq!(visited, vec![0, 2]);
+
+        let (_, components) = Query::new(&entities).with_component::<f32>()?.run();
+        let borrowed_float = components[0][1].borrow();
+        assert_eq!(*borrowed_float.downcast_ref::<f32>().unwrap(), 94.0);
+        Ok(())
+    }
+
+    #[test]
+    fn for_each_on_unregistered_component_fails() {
+        let entities = Entities::default();
+        let result = Query::new(&entities).for_each::<(u32,), _>(|_, _| {});
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn for_each_with_a_repeated_component_fails() {
+        let mut entities = Entities::default();
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32).unwrap();
+
+        let mut visited = false;
+        let result = Query::new(&entities).for_each::<(u32, u32), _>(|_, _| visited = true);
+        assert!(matches!(result, Err(JellyEcsError::DuplicateQueryComponent { component }) if component == "u32"));
+        assert!(!visited);
+    }
+
+    #[test]
+    fn iter_combinations_with_too_few_entities_is_empty() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<u32>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?;
+
+        let mut query = Query::new(&entities);
+        query.with_component::<u32>()?;
+
+        assert_eq!(query.iter_combinations::<3>().count(), 0);
+        assert_eq!(query.iter_combinations::<0>().count(), 0);
+        assert_eq!(query.iter_combinations::<2>().count(), 1);
+        assert_eq!(query.iter_combinations::<1>().count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn disabled_entities_are_skipped_unless_asked_for() -> Result<()> {
+        let mut entities = Entities::default();
+        entities.register_component::<u32>();
+        entities.register_component::<Disabled>();
+        entities.create_entity().with_component(1_u32)?;
+        entities.create_entity().with_component(2_u32)?.with_component(Disabled)?;
+
+        let mut query = Query::new(&entities);
+        query.with_component::<u32>()?;
+        assert_eq!(query.run().0, vec![0]);
+        assert_eq!(query.include_disabled().run().0, vec![0, 1]);
+
+        let mut query = Query::new(&entities);
+        query.with_component::<u32>()?.with_component::<Disabled>()?;
+        assert_eq!(query.run().0, vec![1]);
+        Ok(())
+    }
 }
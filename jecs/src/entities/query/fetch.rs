@@ -0,0 +1,44 @@
+use crate::data::Data;
+use crate::entities::ComponentRefMut;
+use std::any::{type_name, TypeId};
+
+// A tuple of component types that a query hands out as mutable references.
+pub trait Fetch {
+    type Item<'a>;
+
+    fn type_ids() -> Vec<TypeId>;
+
+    fn type_names() -> Vec<&'static str>;
+
+    fn fetch<'a>(borrows: &'a mut [ComponentRefMut<'_>]) -> Self::Item<'a>;
+}
+
+macro_rules! impl_fetch {
+    ($($name:ident),+) => {
+        impl<$($name: Data),+> Fetch for ($($name,)+) {
+            type Item<'a> = ($(&'a mut $name,)+);
+
+            fn type_ids() -> Vec<TypeId> {
+                vec![$(TypeId::of::<$name>()),+]
+            }
+
+            fn type_names() -> Vec<&'static str> {
+                vec![$(type_name::<$name>()),+]
+            }
+
+            fn fetch<'a>(borrows: &'a mut [ComponentRefMut<'_>]) -> Self::Item<'a> {
+                let mut borrows = borrows.iter_mut();
+                ($(borrows.next().unwrap().downcast_mut::<$name>().unwrap(),)+)
+            }
+        }
+    };
+}
+
+impl_fetch!(A);
+impl_fetch!(A, B);
+impl_fetch!(A, B, C);
+impl_fetch!(A, B, C, D);
+impl_fetch!(A, B, C, D, E);
+impl_fetch!(A, B, C, D, E, F);
+impl_fetch!(A, B, C, D, E, F, G);
+impl_fetch!(A, B, C, D, E, F, G, H);
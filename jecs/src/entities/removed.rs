@@ -0,0 +1,152 @@
+use crate::entities::{ComponentId, Entity};
+use crate::World;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+
+// Every entity that lost a component, by component. Entries are kept until
+// the end of the update after the one they were logged in, so a system that
+// runs once per update sees each of them.
+#[derive(Debug, Default)]
+pub(crate) struct RemovalLog {
+    removed: HashMap<ComponentId, Removals>,
+}
+
+#[derive(Debug, Default)]
+struct Removals {
+    // The position of `entities[0]` since tracking started.
+    start: usize,
+    entities: Vec<Entity>,
+    // How many entries were there at the last trim.
+    previous: usize,
+}
+
+impl RemovalLog {
+    pub(crate) fn log(&mut self, id: ComponentId, entity: Entity) {
+        self.removed.entry(id).or_default().entities.push(entity);
+    }
+
+    // The entities logged at or after `cursor`, and the cursor past them.
+    pub(crate) fn read(&self, id: ComponentId, cursor: usize) -> (&[Entity], usize) {
+        match self.removed.get(&id) {
+            Some(removals) => {
+                let skipped = cursor.saturating_sub(removals.start).min(removals.entities.len());
+                (
+                    &removals.entities[skipped..],
+                    removals.start + removals.entities.len(),
+                )
+            }
+            None => (&[], cursor),
+        }
+    }
+
+    // Where each component's log ends, to `truncate` back to.
+    pub(crate) fn ends(&self) -> HashMap<ComponentId, usize> {
+        self.removed
+            .iter()
+            .map(|(id, removals)| (*id, removals.start + removals.entities.len()))
+            .collect()
+    }
+
+    // Forgets removals logged since `ends` was taken, e.g. by a transaction
+    // that was rolled back.
+    pub(crate) fn truncate(&mut self, ends: &HashMap<ComponentId, usize>) {
+        for (id, removals) in &mut self.removed {
+            let end = ends.get(id).copied().unwrap_or(0);
+            let len = end.saturating_sub(removals.start).min(removals.entities.len());
+            removals.entities.truncate(len);
+            removals.previous = removals.previous.min(len);
+        }
+    }
+
+    pub(crate) fn trim(&mut self) {
+        for removals in self.removed.values_mut() {
+            removals.entities.drain(..removals.previous);
+            removals.start += removals.previous;
+            removals.previous = removals.entities.len();
+        }
+    }
+}
+
+// Reads which entities lost their `T`, because it was removed or because
+// they were despawned, since this reader last read. Readers keep their own
+// cursor, so several systems can follow the same removals; keep one in a
+// system's closure or in a resource.
+pub struct RemovedComponents<T> {
+    cursor: usize,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Any> RemovedComponents<T> {
+    pub fn new() -> Self {
+        Self {
+            cursor: 0,
+            marker: PhantomData,
+        }
+    }
+
+    pub fn read(&mut self, world: &World) -> Vec<Entity> {
+        let (entities, cursor) = world
+            .entities
+            .removed
+            .read(ComponentId::of::<T>(), self.cursor);
+        self.cursor = cursor;
+        entities.to_vec()
+    }
+}
+
+impl<T: Any> Default for RemovedComponents<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Debug for RemovedComponents<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemovedComponents")
+            .field("cursor", &self.cursor)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_outlive_one_trim() {
+        let mut log = RemovalLog::default();
+        let id = ComponentId::of::<u32>();
+        log.log(id, entity(0));
+        log.trim();
+        log.log(id, entity(1));
+
+        assert_eq!(log.read(id, 0), (&[entity(0), entity(1)][..], 2));
+        assert_eq!(log.read(id, 1), (&[entity(1)][..], 2));
+
+        log.trim();
+        assert_eq!(log.read(id, 0), (&[entity(1)][..], 2));
+        log.trim();
+        assert_eq!(log.read(id, 0), (&[][..], 2));
+        assert_eq!(log.read(ComponentId::of::<f32>(), 3), (&[][..], 3));
+    }
+
+    #[test]
+    fn truncating_forgets_later_entries() {
+        let mut log = RemovalLog::default();
+        let id = ComponentId::of::<u32>();
+        log.log(id, entity(0));
+        let ends = log.ends();
+        log.log(id, entity(1));
+        log.log(ComponentId::of::<f32>(), entity(2));
+
+        log.truncate(&ends);
+        assert_eq!(log.read(id, 0), (&[entity(0)][..], 1));
+        assert_eq!(log.read(ComponentId::of::<f32>(), 0), (&[][..], 0));
+    }
+
+    fn entity(id: usize) -> Entity {
+        Entity { id, generation: 0 }
+    }
+}
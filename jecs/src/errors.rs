@@ -1,11 +1,215 @@
+use crate::entities::dynamic::FieldType;
+use crate::entities::Entity;
+use std::error::Error as StdError;
 use thiserror::Error;
 
+// Systems, commands and timer actions return it too; `eyre::Report` converts
+// into `JellyEcsError` with `?`, and the other way round. A report that wraps a
+// `JellyEcsError` converts back into that same error.
+pub type Result<T, E = JellyEcsError> = std::result::Result<T, E>;
+
+// Errors that mention an entity carry its handle, and its `Name` when it has
+// one, so messages read `entity 3 "player"`. `entity` is optional on errors
+// that can also come up without one, such as from a query.
 #[derive(Debug, Error)]
 pub enum JellyEcsError {
     #[error("Attempted to add a component to an entity without calling create_entity first")]
     CreateEntityNeverCalled,
-    #[error("Attempted to reference a component that wasn't registered")]
-    ComponentNotRegistered,
-    #[error("Attempted to reference an entity that doesn't exist")]
-    EntityDoesNotExist,
+    #[error(
+        "Attempted to reference a component that wasn't registered: {component}{}",
+        on_entity(.entity, .name)
+    )]
+    ComponentNotRegistered {
+        entity: Option<Entity>,
+        name: Option<String>,
+        component: String,
+    },
+    #[error("Query asks for {component} more than once, it can't be borrowed mutably twice")]
+    DuplicateQueryComponent { component: String },
+    #[error("Attempted to reference an entity that doesn't exist: {}", describe_entity(.entity, .name))]
+    EntityDoesNotExist { entity: Entity, name: Option<String> },
+    #[error("Attempted to reference a {component} component that {} doesn't have", describe_entity(.entity, .name))]
+    ComponentNotFound {
+        entity: Entity,
+        name: Option<String>,
+        component: String,
+    },
+    #[error(
+        "Attempted to clone a {component} component on {} that wasn't registered as cloneable",
+        describe_entity(.entity, .name)
+    )]
+    ComponentNotCloneable {
+        entity: Entity,
+        name: Option<String>,
+        component: String,
+    },
+    #[error("Dynamic component {component} has no field named {field}")]
+    FieldDoesNotExist { component: String, field: String },
+    #[error("Field {field} of dynamic component {component} is {expected:?}, not {found:?}")]
+    FieldTypeMismatch {
+        component: String,
+        field: String,
+        expected: FieldType,
+        found: FieldType,
+    },
+    #[error("Component name {component} is ambiguous, it could be any of {}", .candidates.join(", "))]
+    AmbiguousComponentName { component: String, candidates: Vec<String> },
+    #[error(
+        "Dynamic component {component} wasn't created from this world's layout{}",
+        on_entity(.entity, .name)
+    )]
+    DynamicLayoutMismatch {
+        entity: Option<Entity>,
+        name: Option<String>,
+        component: String,
+    },
+    #[error("There is no prefab named {prefab}{}", did_you_mean(.suggestion))]
+    PrefabNotFound { prefab: String, suggestion: Option<String> },
+    #[error(
+        "Prefab {prefab} references a component that isn't registered: {component}{}",
+        did_you_mean(.suggestion)
+    )]
+    UnknownPrefabComponent {
+        prefab: String,
+        component: String,
+        suggestion: Option<String>,
+    },
+    #[error("Prefab {prefab} has no components to spawn")]
+    EmptyPrefab { prefab: String },
+    #[error("Prefab {prefab} uses {component}, which wasn't registered as deserializable")]
+    ComponentNotDeserializable { prefab: String, component: String },
+    #[error("Prefab {prefab} has an invalid {component} component: {error}")]
+    InvalidPrefabComponent {
+        prefab: String,
+        component: String,
+        error: String,
+    },
+    #[error("Invalid prefab file: {0}")]
+    InvalidPrefabFile(String),
+    #[error("Invalid prefab overrides: {0}")]
+    InvalidPrefabOverrides(String),
+    #[error("Attempted to restore {component} components, which this world can't clone, from a snapshot")]
+    SnapshotMismatch { component: String },
+    #[error("Attempted to replicate a component that wasn't registered as replicated: {component}")]
+    ComponentNotReplicated { component: String },
+    #[error("Replication failed: {0}")]
+    ReplicationFailed(String),
+    #[error(
+        "Server entity {} (generation {}) hasn't been replicated to this client",
+        .entity.id,
+        .entity.generation
+    )]
+    UnknownServerEntity { entity: Entity },
+    #[error(
+        "A copied component references entity {} (generation {}), which wasn't copied along",
+        .entity.id,
+        .entity.generation
+    )]
+    EntityNotCopied { entity: Entity },
+    // Resources that fail to hash have no entity, `component` is their type.
+    #[error("Could not hash a {component}{}: {error}", on_entity(.entity, .name))]
+    HashFailed {
+        entity: Option<Entity>,
+        name: Option<String>,
+        component: String,
+        error: String,
+    },
+    #[error("Attempted to send a command that wasn't registered: {0}")]
+    CommandNotRegistered(String),
+    #[error("Invalid replay: {0}")]
+    InvalidReplay(String),
+    #[error("Replay diverged at tick {0}: expected world hash {1:016x}, got {2:016x}")]
+    ReplayDiverged(usize, u64, u64),
+    #[error("Invalid fixed update rate: {0} Hz")]
+    InvalidTickRate(f64),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    // Errors from user code run by jecs, such as systems and commands.
+    #[error(transparent)]
+    Other(Box<dyn StdError + Send + Sync>),
+}
+
+impl JellyEcsError {
+    pub fn other(error: impl StdError + Send + Sync + 'static) -> Self {
+        JellyEcsError::Other(Box::new(error))
+    }
+}
+
+impl From<eyre::Report> for JellyEcsError {
+    fn from(report: eyre::Report) -> Self {
+        report
+            .downcast::<JellyEcsError>()
+            .unwrap_or_else(|report| JellyEcsError::Other(report.into()))
+    }
+}
+
+pub(crate) fn describe_entity(entity: &Entity, name: &Option<String>) -> String {
+    match name {
+        Some(name) => format!("entity {} {:?}", entity.id, name),
+        None => format!("entity {}", entity.id),
+    }
+}
+
+fn on_entity(entity: &Option<Entity>, name: &Option<String>) -> String {
+    entity
+        .map(|entity| format!(" (on {})", describe_entity(&entity, name)))
+        .unwrap_or_default()
+}
+
+fn did_you_mean(suggestion: &Option<String>) -> String {
+    suggestion
+        .as_ref()
+        .map(|suggestion| format!(" (did you mean {}?)", suggestion))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_and_from_eyre() {
+        let error: JellyEcsError = eyre::eyre!("boom").into();
+        assert!(matches!(error, JellyEcsError::Other(_)));
+        assert_eq!(error.to_string(), "boom");
+
+        let report: eyre::Report = JellyEcsError::ComponentNotRegistered {
+            entity: None,
+            name: None,
+            component: "Health".to_owned(),
+        }
+        .into();
+        assert!(matches!(
+            report.downcast_ref::<JellyEcsError>(),
+            Some(JellyEcsError::ComponentNotRegistered { entity: None, .. })
+        ));
+
+        let error: JellyEcsError = report.into();
+        assert!(matches!(error, JellyEcsError::ComponentNotRegistered { entity: None, .. }));
+    }
+
+    #[test]
+    fn entity_errors_name_the_entity() {
+        let entity = Entity { id: 3, generation: 1 };
+        let error = JellyEcsError::ComponentNotFound {
+            entity,
+            name: Some("player".to_owned()),
+            component: "Health".to_owned(),
+        };
+
+        assert_eq!(
+            error.to_string(),
+            "Attempted to reference a Health component that entity 3 \"player\" doesn't have"
+        );
+
+        let error = JellyEcsError::ComponentNotRegistered {
+            entity: Some(entity),
+            name: Some("player".to_owned()),
+            component: "Armor".to_owned(),
+        };
+        assert_eq!(
+            error.to_string(),
+            "Attempted to reference a component that wasn't registered: Armor (on entity 3 \"player\")"
+        );
+    }
 }
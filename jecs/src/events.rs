@@ -0,0 +1,89 @@
+use crate::data::Data;
+
+// A queue of events of one type, stored as a resource. Events stay pending
+// until a reader drains them, or until the end of the update after the one
+// they were sent in, so nobody has to drain events they don't care about.
+#[derive(Debug)]
+pub struct Events<E: Data> {
+    pending: Vec<E>,
+    // How many of `pending` were already there at the last `update`.
+    previous: usize,
+}
+
+impl<E: Data> Default for Events<E> {
+    fn default() -> Self {
+        Self { pending: vec![], previous: 0 }
+    }
+}
+
+impl<E: Data> Events<E> {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn send(&mut self, event: E) {
+        self.pending.push(event);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &E> {
+        self.pending.iter()
+    }
+
+    pub fn drain(&mut self) -> Vec<E> {
+        self.previous = 0;
+        std::mem::take(&mut self.pending)
+    }
+
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.pending.truncate(len);
+        self.previous = self.previous.min(len);
+    }
+
+    // Drops the events that were already pending at the previous call.
+    pub fn update(&mut self) {
+        self.pending.drain(..self.previous);
+        self.previous = self.pending.len();
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_and_drain_events() {
+        let mut events = Events::new();
+        assert!(events.is_empty());
+
+        events.send(Bitten(1));
+        events.send(Bitten(2));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events.iter().map(|event| event.0).collect::<Vec<_>>(), vec![1, 2]);
+
+        let drained: Vec<usize> = events.drain().into_iter().map(|event| event.0).collect();
+        assert_eq!(drained, vec![1, 2]);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn undrained_events_last_one_update() {
+        let mut events = Events::new();
+        events.send(Bitten(1));
+        events.update();
+        events.send(Bitten(2));
+        assert_eq!(events.len(), 2);
+
+        events.update();
+        assert_eq!(events.iter().map(|event| event.0).collect::<Vec<_>>(), vec![2]);
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    struct Bitten(pub usize);
+}
@@ -0,0 +1,198 @@
+use crate::entities::{Component, ComponentId, Entities, Entity};
+
+type EntityComponents = Vec<(ComponentId, Component)>;
+
+// A reversible change to the world. Component handles are kept rather than
+// copied, so undoing a removal brings back the very same component.
+#[derive(Debug, Clone)]
+pub(crate) enum Operation {
+    // The components are only captured when the spawn is undone, since the
+    // entity is filled in after `create_entity` returns.
+    Spawn { entity: Entity, components: EntityComponents },
+    Despawn { entity: Entity, components: EntityComponents },
+    Add { entity: Entity, id: ComponentId, component: Component },
+    Remove { entity: Entity, id: ComponentId, component: Component },
+    Set { entity: Entity, id: ComponentId, old: Component, new: Component },
+}
+
+impl Operation {
+    pub(crate) fn undo(&mut self, entities: &mut Entities) {
+        match self {
+            Operation::Spawn { entity, components } => *components = entities.take_entity(entity.id),
+            Operation::Despawn { entity, components } => entities.restore_entity(*entity, components.clone()),
+            Operation::Add { entity, id, .. } => {
+                entities.take_component(*id, entity.id);
+            }
+            Operation::Remove { entity, id, component } => entities.put_component(*id, entity.id, component.clone()),
+            Operation::Set { entity, id, old, .. } => entities.put_component(*id, entity.id, old.clone()),
+        }
+    }
+
+    pub(crate) fn redo(&mut self, entities: &mut Entities) {
+        match self {
+            Operation::Spawn { entity, components } => {
+                entities.restore_entity(*entity, std::mem::take(components))
+            }
+            Operation::Despawn { entity, .. } => {
+                entities.take_entity(entity.id);
+            }
+            Operation::Add { entity, id, component } => entities.put_component(*id, entity.id, component.clone()),
+            Operation::Remove { entity, id, .. } => {
+                entities.take_component(*id, entity.id);
+            }
+            Operation::Set { entity, id, new, .. } => entities.put_component(*id, entity.id, new.clone()),
+        }
+    }
+}
+
+// Opt-in undo history, see `World::enable_journal`. Every operation outside a
+// transaction is its own undo step; a transaction is undone as a whole.
+#[derive(Debug, Default)]
+pub struct Journal {
+    undo: Vec<Vec<Operation>>,
+    redo: Vec<Vec<Operation>>,
+    transaction: Option<Vec<Operation>>,
+    depth: usize,
+}
+
+impl Journal {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.depth > 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(crate) fn record(&mut self, operation: Operation) {
+        self.redo.clear();
+        match &mut self.transaction {
+            Some(operations) => operations.push(operation),
+            None => self.undo.push(vec![operation]),
+        }
+    }
+
+    // Transactions nest; only the outermost commit closes the undo step.
+    pub(crate) fn begin_transaction(&mut self) {
+        if self.depth == 0 {
+            self.transaction = Some(vec![]);
+        }
+        self.depth += 1;
+    }
+
+    pub(crate) fn commit_transaction(&mut self) {
+        if self.depth == 0 {
+            return;
+        }
+
+        self.depth -= 1;
+        if self.depth == 0 {
+            if let Some(operations) = self.transaction.take() {
+                if !operations.is_empty() {
+                    self.undo.push(operations);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn transaction_len(&self) -> usize {
+        self.transaction.as_ref().map_or(0, Vec::len)
+    }
+
+    // Drops what was recorded since `len` without undoing it; the caller has
+    // already rolled those operations back.
+    pub(crate) fn rollback_transaction(&mut self, len: usize) {
+        if let Some(operations) = &mut self.transaction {
+            operations.truncate(len);
+        }
+        self.commit_transaction();
+    }
+
+    pub(crate) fn undo(&mut self, entities: &mut Entities) -> bool {
+        self.close_transaction();
+        match self.undo.pop() {
+            Some(mut operations) => {
+                operations
+                    .iter_mut()
+                    .rev()
+                    .for_each(|operation| operation.undo(entities));
+                self.redo.push(operations);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn redo(&mut self, entities: &mut Entities) -> bool {
+        self.close_transaction();
+        match self.redo.pop() {
+            Some(mut operations) => {
+                operations
+                    .iter_mut()
+                    .for_each(|operation| operation.redo(entities));
+                self.undo.push(operations);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Undoing in the middle of a transaction commits what it has so far.
+    fn close_transaction(&mut self) {
+        if self.depth > 0 {
+            self.depth = 1;
+            self.commit_transaction();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_transactions_close_with_the_outermost_commit() {
+        let mut entities = Entities::new();
+        entities.register_component::<u32>();
+        let mut journal = Journal::new();
+
+        journal.begin_transaction();
+        journal.begin_transaction();
+        journal.record(spawn(&mut entities));
+        journal.commit_transaction();
+        assert!(journal.in_transaction());
+        journal.record(spawn(&mut entities));
+        journal.commit_transaction();
+
+        assert!(!journal.in_transaction());
+        assert_eq!(journal.undo_len(), 1);
+        assert!(journal.undo(&mut entities));
+        assert_eq!(entities.iter().count(), 0);
+    }
+
+    fn spawn(entities: &mut Entities) -> Operation {
+        entities.create_entity().with_component(1u32).unwrap();
+        Operation::Spawn {
+            entity: entities.inserting_into_entity(),
+            components: vec![],
+        }
+    }
+}
@@ -1,28 +1,103 @@
+use crate::data::Data;
+use crate::diagnostics::Diagnostics;
+use crate::diff::WorldDiff;
+use crate::dump::WorldDump;
+use crate::entities::builder::EntityBuilder;
+use crate::entities::disabled::Disabled;
+use crate::entities::dynamic::{DynamicComponent, DynamicLayout};
+use crate::entities::map_entities::{EntityMap, MapEntities};
 use crate::entities::query::Query;
-use crate::entities::Entities;
-use crate::resources::{Resources};
-use eyre::Result;
-use std::any::Any;
+use crate::entities::{Component, ComponentId, Entities, Entity};
+use crate::errors::{JellyEcsError, Result};
+use crate::events::Events;
+use crate::journal::{Journal, Operation};
+use crate::prefab::{suggest, Prefabs};
+use crate::relationship::{Relationship, RelationshipRemoved};
+use crate::registry::{ComponentInfo, MapEntitiesFn, Reflect, TypeRegistry};
+use crate::resources::{NonSendResources, Resources};
+use crate::snapshot::WorldSnapshot;
 
+pub use crate::app::{App, Plugin};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::time::Instant;
+
+pub mod app;
+pub mod condition;
+pub mod data;
+pub mod diagnostics;
+pub mod diff;
+pub mod dump;
 pub mod entities;
 pub mod errors;
+pub mod events;
+pub mod journal;
+pub mod prefab;
+pub mod registry;
+pub mod relationship;
+pub mod replay;
+pub mod replication;
 pub mod resources;
+pub mod rng;
+pub mod snapshot;
+pub mod state;
+pub mod time;
+pub mod timer;
+
+type CopiedComponent = (ComponentId, String, Component, Option<MapEntitiesFn>);
+
+// Reaches the `Events<E>` resources of every event type sent so far without
+// knowing their types.
+#[derive(Clone, Copy)]
+struct EventQueue {
+    update: fn(&mut Resources),
+    len: fn(&Resources) -> usize,
+    truncate: fn(&mut Resources, usize),
+}
+
+impl EventQueue {
+    fn of<E: Data>() -> Self {
+        Self {
+            update: |resources| resources.get_mut::<Events<E>>().into_iter().for_each(Events::update),
+            len: |resources| resources.get_ref::<Events<E>>().map_or(0, Events::len),
+            truncate: |resources, len| {
+                if let Some(events) = resources.get_mut::<Events<E>>() {
+                    events.truncate(len);
+                }
+            },
+        }
+    }
+}
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct World {
     resources: Resources,
+    non_send_resources: NonSendResources,
     entities: Entities,
+    registry: TypeRegistry,
+    journal: Option<Journal>,
+    rollback_logs: Vec<Vec<Operation>>,
+    event_queues: HashMap<TypeId, EventQueue>,
 }
 
 impl World {
     pub fn new() -> Self {
         Self {
             resources: Resources::new(),
-            entities: Entities::new()
+            non_send_resources: NonSendResources::new(),
+            entities: Entities::new(),
+            registry: TypeRegistry::new(),
+            journal: None,
+            rollback_logs: vec![],
+            event_queues: HashMap::new(),
         }
     }
 
-    pub fn add_resource(&mut self, resource: impl Any) {
+    pub fn add_resource(&mut self, resource: impl Data) {
         self.resources.add(resource);
     }
 
@@ -38,28 +113,756 @@ impl World {
         self.resources.remove::<T>();
     }
 
-    pub fn register_component<T: Any>(&mut self) {
+    pub fn send_event<E: Data>(&mut self, event: E) {
+        self.event_queues
+            .entry(TypeId::of::<E>())
+            .or_insert_with(EventQueue::of::<E>);
+        match self.get_resource_mut::<Events<E>>() {
+            Some(events) => events.send(event),
+            None => {
+                let mut events = Events::new();
+                events.send(event);
+                self.add_resource(events);
+            }
+        }
+    }
+
+    pub fn drain_events<E: Data>(&mut self) -> Vec<E> {
+        self.get_resource_mut::<Events<E>>()
+            .map(Events::drain)
+            .unwrap_or_default()
+    }
+
+    // Drops events nobody drained since the previous call, see
+    // `Events::update`. `App::update` calls it after every update.
+    pub fn update_events(&mut self) {
+        for queue in self.event_queues.values() {
+            (queue.update)(&mut self.resources);
+        }
+    }
+
+    fn event_lens(&self) -> HashMap<TypeId, usize> {
+        self.event_queues
+            .iter()
+            .map(|(type_id, queue)| (*type_id, (queue.len)(&self.resources)))
+            .collect()
+    }
+
+    // Drops events sent since `event_lens` was taken.
+    fn truncate_events(&mut self, lens: &HashMap<TypeId, usize>) {
+        for (type_id, queue) in &self.event_queues {
+            (queue.truncate)(&mut self.resources, lens.get(type_id).copied().unwrap_or(0));
+        }
+    }
+
+    pub fn add_non_send_resource(&mut self, resource: impl Any) {
+        self.non_send_resources.add(resource);
+    }
+
+    pub fn get_non_send_resource<T: Any>(&self) -> Option<&T> {
+        self.non_send_resources.get_ref::<T>()
+    }
+
+    pub fn get_non_send_resource_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.non_send_resources.get_mut::<T>()
+    }
+
+    pub fn delete_non_send_resource<T: Any>(&mut self) {
+        self.non_send_resources.remove::<T>();
+    }
+
+    pub fn register_cloneable_resource<T: Data + Clone>(&mut self) {
+        self.resources.register_cloneable::<T>();
+    }
+
+    pub fn register_component<T: Data>(&mut self) {
         self.entities.register_component::<T>();
+        self.registry.register::<T>();
+    }
+
+    pub fn register_cloneable_component<T: Data + Clone>(&mut self) {
+        self.entities.register_cloneable_component::<T>();
+        self.registry.register::<T>();
+    }
+
+    pub fn register_reflected_component<T: Data + Reflect>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_reflect::<T>();
+    }
+
+    pub fn register_deserializable_component<T: Data + DeserializeOwned>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_deserialize::<T>();
+    }
+
+    pub fn register_map_entities<T: Data + MapEntities>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_map_entities::<T>();
+    }
+
+    // Only components registered this way are sent by `ReplicationServer`.
+    pub fn register_replicated_component<T: Data + Serialize + DeserializeOwned>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_replicate::<T>();
+    }
+
+    // Hashed components are what `World::hash` compares between runs.
+    pub fn register_hashed_component<T: Data + Serialize>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_hash::<T>();
+    }
+
+    // Hashed resources go into `World::hash` after the entities.
+    pub fn register_hashed_resource<T: Data + Serialize>(&mut self) {
+        self.resources.register_hashed::<T>();
+    }
+
+    pub fn register_dynamic_component(&mut self, layout: DynamicLayout) -> ComponentId {
+        let name = layout.name().to_owned();
+        let id = self.entities.register_dynamic_component(layout);
+        self.registry.register_dynamic(id, &name);
+        id
+    }
+
+    pub fn new_dynamic_component(&self, id: ComponentId) -> Result<DynamicComponent> {
+        self.entities.new_dynamic_component(id)
     }
 
-    pub fn create_entity(&mut self) -> &mut Entities {
-        self.entities.create_entity()
+    pub fn register_debug<T: Any + Debug>(&mut self) {
+        self.registry.register_debug::<T>();
     }
 
-    pub fn query(&self) -> Query {
+    pub fn registry(&self) -> &TypeRegistry {
+        &self.registry
+    }
+
+    // The builder's transaction is committed when it is dropped.
+    pub fn create_entity(&mut self) -> EntityBuilder<'_> {
+        self.begin_transaction();
+        self.entities.create_entity();
+        self.record_spawn();
+        EntityBuilder::new(self)
+    }
+
+    pub fn query(&self) -> Query<'_> {
         Query::new(&self.entities)
     }
 
     pub fn delete_component_by_entity_id<T: Any>(&mut self, index: usize) -> Result<()> {
-        self.entities.delete_component_by_entity_id::<T>(index)
+        let id = ComponentId::of::<T>();
+        let (entity, removed) = (self.entities.entity(index), self.entities.get_component(id, index).cloned());
+        self.entities.delete_component_by_entity_id::<T>(index)?;
+        self.record_remove(entity, id, removed);
+        Ok(())
+    }
+
+    pub fn add_component_by_entity_id(&mut self, data: impl Data, index: usize) -> Result<()> {
+        let id = ComponentId::Type(data.type_id());
+        let previous = self.entities.get_component(id, index).cloned();
+        self.entities.add_component_by_entity_id(data, index)?;
+        self.record_insert(id, index, previous);
+        Ok(())
+    }
+
+    pub fn add_dynamic_component_by_entity_id(&mut self, data: DynamicComponent, index: usize) -> Result<()> {
+        let id = data.id();
+        let previous = self.entities.get_component(id, index).cloned();
+        self.entities.add_dynamic_component_by_entity_id(data, index)?;
+        self.record_insert(id, index, previous);
+        Ok(())
+    }
+
+    pub(crate) fn add_raw_component(
+        &mut self,
+        id: ComponentId,
+        name: String,
+        component: Component,
+        index: usize,
+    ) -> Result<()> {
+        let previous = self.entities.get_component(id, index).cloned();
+        self.entities.insert_into_entity(id, name, component, index)?;
+        self.record_insert(id, index, previous);
+        Ok(())
+    }
+
+    // Like `add_component_by_entity_id`, but the entity has to have the
+    // component already.
+    pub fn set_component<T: Data>(&mut self, data: T, index: usize) -> Result<()> {
+        let id = ComponentId::of::<T>();
+        if self.entities.get_bit_mask(&id).is_some() && !self.entities.has_component(id, index) {
+            return Err(self.entities.component_not_found(type_name::<T>().to_owned(), index));
+        }
+
+        self.add_component_by_entity_id(data, index)
     }
 
-    pub fn add_component_by_entity_id(&mut self, data: impl Any, index: usize) -> Result<()> {
-        self.entities.add_component_by_entity_id(data, index)
+    pub fn delete_component_by_id(&mut self, id: ComponentId, index: usize) -> Result<()> {
+        let (entity, removed) = (self.entities.entity(index), self.entities.get_component(id, index).cloned());
+        self.entities.delete_component_by_id(id, index)?;
+        self.record_remove(entity, id, removed);
+        Ok(())
     }
 
+    // Relationships pointing at the entity are removed along with it, in the
+    // same undo step.
     pub fn delete_entity_by_id(&mut self, index: usize) -> Result<()> {
-        self.entities.delete_entity_by_id(index)
+        let target = match self.entities.entity(index) {
+            Some(target) => target,
+            None => return self.despawn(index),
+        };
+        let relationships = self.relationships_to(target);
+        if relationships.is_empty() {
+            return self.despawn(index);
+        }
+
+        self.begin_transaction();
+        let result = self
+            .despawn(index)
+            .and_then(|()| self.remove_relationships(target, relationships));
+        self.commit_transaction();
+        result
+    }
+
+    fn despawn(&mut self, index: usize) -> Result<()> {
+        let entity = self.entities.entity(index);
+        let components = match entity {
+            Some(_) if self.is_recording() => self.entities.entity_components(index),
+            _ => vec![],
+        };
+        self.entities.delete_entity_by_id(index)?;
+
+        if let Some(entity) = entity {
+            self.record(Operation::Despawn { entity, components });
+        }
+        Ok(())
+    }
+
+    // Records spawns, despawns and component changes made through the `World`
+    // from now on, so they can be undone. Writes made through queries aren't
+    // tracked.
+    pub fn enable_journal(&mut self) {
+        if self.journal.is_none() {
+            self.journal = Some(Journal::new());
+        }
+    }
+
+    pub fn disable_journal(&mut self) {
+        self.journal = None;
+    }
+
+    pub fn journal(&self) -> Option<&Journal> {
+        self.journal.as_ref()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match &mut self.journal {
+            Some(journal) => journal.undo(&mut self.entities),
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match &mut self.journal {
+            Some(journal) => journal.redo(&mut self.entities),
+            None => false,
+        }
+    }
+
+    // Everything recorded until the matching commit is undone in one step.
+    pub fn begin_transaction(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.begin_transaction();
+        }
+    }
+
+    pub fn commit_transaction(&mut self) {
+        if let Some(journal) = &mut self.journal {
+            journal.commit_transaction();
+        }
+    }
+
+    // Runs `edits` and, if it returns an error, undoes every spawn, despawn
+    // and component change it made through the `World` before passing the
+    // error on. Events sent and removals logged meanwhile are dropped too,
+    // but resources aren't rolled back. With the journal enabled, a successful
+    // transaction is a single undo step.
+    pub fn transaction<R>(&mut self, edits: impl FnOnce(&mut World) -> Result<R>) -> Result<R> {
+        self.begin_transaction();
+        let journal_len = self.journal.as_ref().map_or(0, Journal::transaction_len);
+        let events = self.event_lens();
+        let removals = self.entities.removed.ends();
+        self.rollback_logs.push(vec![]);
+
+        let result = edits(self);
+        let mut operations = self.rollback_logs.pop().unwrap_or_default();
+        match result {
+            Ok(value) => {
+                if let Some(parent) = self.rollback_logs.last_mut() {
+                    parent.append(&mut operations);
+                }
+                self.commit_transaction();
+                Ok(value)
+            }
+            Err(error) => {
+                operations
+                    .iter_mut()
+                    .rev()
+                    .for_each(|operation| operation.undo(&mut self.entities));
+                if let Some(journal) = &mut self.journal {
+                    journal.rollback_transaction(journal_len);
+                }
+                self.truncate_events(&events);
+                self.entities.removed.truncate(&removals);
+                Err(error)
+            }
+        }
+    }
+
+    fn is_recording(&self) -> bool {
+        self.journal.is_some() || !self.rollback_logs.is_empty()
+    }
+
+    fn record(&mut self, operation: Operation) {
+        if let Some(operations) = self.rollback_logs.last_mut() {
+            operations.push(operation.clone());
+        }
+        if let Some(journal) = &mut self.journal {
+            journal.record(operation);
+        }
+    }
+
+    fn record_spawn(&mut self) {
+        if self.is_recording() {
+            self.record(Operation::Spawn {
+                entity: self.entities.inserting_into_entity(),
+                components: vec![],
+            });
+        }
+    }
+
+    fn record_insert(&mut self, id: ComponentId, index: usize, previous: Option<Component>) {
+        if !self.is_recording() {
+            return;
+        }
+
+        if let (Some(entity), Some(component)) = (self.entities.entity(index), self.entities.get_component(id, index)) {
+            let component = component.clone();
+            self.record(match previous {
+                Some(old) => Operation::Set { entity, id, old, new: component },
+                None => Operation::Add { entity, id, component },
+            });
+        }
+    }
+
+    fn record_remove(&mut self, entity: Option<Entity>, id: ComponentId, removed: Option<Component>) {
+        if let (Some(entity), Some(component)) = (entity, removed) {
+            self.record(Operation::Remove { entity, id, component });
+        }
+    }
+
+    pub fn entity(&self, index: usize) -> Option<Entity> {
+        self.entities.entity(index)
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+
+    pub fn register_relationship<T: Relationship>(&mut self) {
+        if self.entities.get_bit_mask(&ComponentId::of::<T>()).is_none() {
+            self.entities.register_component::<T>();
+        }
+        self.registry.register_relationship::<T>();
+    }
+
+    // Every entity whose `T` points at `target`, disabled ones included.
+    pub fn sources<T: Relationship>(&self, target: Entity) -> Vec<Entity> {
+        self.registry
+            .get(&ComponentId::of::<T>())
+            .map(|info| self.sources_of(info, target))
+            .unwrap_or_default()
+    }
+
+    // Targets are read off the components themselves, since a query can
+    // retarget a relationship in place.
+    fn sources_of(&self, info: &ComponentInfo, target: Entity) -> Vec<Entity> {
+        self.entities
+            .iter()
+            .filter(|source| {
+                self.entities
+                    .get_component(info.id(), source.id)
+                    .and_then(|component| info.target(&*component.borrow()))
+                    == Some(target)
+            })
+            .collect()
+    }
+
+    fn relationships_to(&self, target: Entity) -> Vec<(Entity, ComponentId, String)> {
+        let mut relationships = vec![];
+        for info in self.registry.iter().filter(|info| info.is_relationship()) {
+            for source in self.sources_of(info, target) {
+                relationships.push((source, info.id(), info.short_name().to_owned()));
+            }
+        }
+
+        relationships.sort_by_key(|(source, _, name)| (name.clone(), *source));
+        relationships
+    }
+
+    fn remove_relationships(&mut self, target: Entity, relationships: Vec<(Entity, ComponentId, String)>) -> Result<()> {
+        for (source, id, relationship) in relationships {
+            // A relationship to itself went away with the despawned entity.
+            if self.is_alive(source) {
+                self.delete_component_by_id(id, source.id)?;
+                self.send_event(RelationshipRemoved { source, target, relationship });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Takes the entity out of queries, see `Disabled`, without touching its
+    // components. Disabling goes through the journal like any other insert.
+    pub fn disable(&mut self, entity: Entity) -> Result<()> {
+        if !self.is_alive(entity) {
+            return Err(self.entities.entity_does_not_exist(entity.id));
+        }
+        if self.is_disabled(entity) {
+            return Ok(());
+        }
+
+        if self.entities.get_bit_mask(&ComponentId::of::<Disabled>()).is_none() {
+            self.register_cloneable_component::<Disabled>();
+        }
+        self.add_component_by_entity_id(Disabled, entity.id)
+    }
+
+    pub fn enable(&mut self, entity: Entity) -> Result<()> {
+        if !self.is_alive(entity) {
+            return Err(self.entities.entity_does_not_exist(entity.id));
+        }
+        if !self.is_disabled(entity) {
+            return Ok(());
+        }
+
+        self.delete_component_by_id(ComponentId::of::<Disabled>(), entity.id)
+    }
+
+    pub fn is_disabled(&self, entity: Entity) -> bool {
+        self.is_alive(entity) && self.entities.has_component(ComponentId::of::<Disabled>(), entity.id)
+    }
+
+    // Forgets removals read by `RemovedComponents` that happened before the
+    // previous call. `App::update` calls it after every update; worlds run
+    // by hand should too, or the removal log keeps growing.
+    pub fn trim_removed_components(&mut self) {
+        self.entities.removed.trim();
+    }
+
+    pub fn find_by_name(&self, name: &str) -> Option<Entity> {
+        self.entities.find_by_name(name)
+    }
+
+    pub fn find_all_by_name(&self, name: &str) -> Vec<Entity> {
+        self.entities.find_all_by_name(name)
+    }
+
+    pub fn spawn_prefab(&mut self, name: &str) -> Result<Entity> {
+        self.spawn_prefab_with(name, &Value::Null)
+    }
+
+    // Every component is deserialized before the entity is created, so a bad
+    // prefab never leaves a half-built entity behind.
+    pub fn spawn_prefab_with(&mut self, name: &str, overrides: &Value) -> Result<Entity> {
+        let prefabs = self
+            .get_resource::<Prefabs>()
+            .ok_or_else(|| JellyEcsError::PrefabNotFound {
+                prefab: name.to_owned(),
+                suggestion: None,
+            })?;
+        let prefab = prefabs
+            .get(name)
+            .ok_or_else(|| JellyEcsError::PrefabNotFound {
+                prefab: name.to_owned(),
+                suggestion: suggest(name, prefabs.names()),
+            })?
+            .with_overrides(overrides)?;
+
+        let mut components = vec![];
+        for (component_name, value) in prefab.components() {
+            let info = self.registry.get_by_name(component_name)?.ok_or_else(|| {
+                let candidates = self
+                    .registry
+                    .iter()
+                    .filter(|info| info.is_deserializable())
+                    .map(|info| info.short_name());
+                JellyEcsError::UnknownPrefabComponent {
+                    prefab: name.to_owned(),
+                    component: component_name.to_owned(),
+                    suggestion: suggest(component_name, candidates),
+                }
+            })?;
+            let component = info
+                .deserialize(value)
+                .ok_or_else(|| JellyEcsError::ComponentNotDeserializable {
+                    prefab: name.to_owned(),
+                    component: info.name().to_owned(),
+                })?
+                .map_err(|error| JellyEcsError::InvalidPrefabComponent {
+                    prefab: name.to_owned(),
+                    component: component_name.to_owned(),
+                    error,
+                })?;
+            components.push((info.id(), info.name().to_owned(), component));
+        }
+        // An entity without components doesn't exist.
+        if components.is_empty() {
+            return Err(JellyEcsError::EmptyPrefab { prefab: name.to_owned() });
+        }
+
+        let mut builder = self.create_entity();
+        for (id, component_name, component) in components {
+            builder.insert(id, component_name, component)?;
+        }
+        drop(builder);
+
+        let index = self.entities.inserting_into_index();
+        self.entities
+            .entity(index)
+            .ok_or_else(|| self.entities.entity_does_not_exist(index))
+    }
+
+    // Every component on the entity has to be registered as cloneable.
+    pub fn clone_entity(&mut self, entity: Entity) -> Result<Entity> {
+        let map = self.clone_entities(&[entity])?;
+        Ok(map.map(entity))
+    }
+
+    // References between the cloned entities point at the clones; references
+    // to anything else are left alone.
+    pub fn clone_entities(&mut self, entities: &[Entity]) -> Result<EntityMap> {
+        let copies = self.copy_components(entities)?;
+        let (map, _) = self.insert_copies(copies)?;
+        Ok(map)
+    }
+
+    pub fn copy_entity_to(&self, entity: Entity, target: &mut World) -> Result<Entity> {
+        let map = self.copy_entities_to(&[entity], target)?;
+        Ok(map.map(entity))
+    }
+
+    // The components have to be registered in `target` too. References to
+    // entities that aren't copied along would dangle in `target`, so they fail
+    // the copy instead.
+    pub fn copy_entities_to(&self, entities: &[Entity], target: &mut World) -> Result<EntityMap> {
+        let copies = self.copy_components(entities)?;
+        target.transaction(|target| {
+            let (map, unmapped) = target.insert_copies(copies)?;
+            match unmapped.first() {
+                Some(entity) => Err(JellyEcsError::EntityNotCopied { entity: *entity }),
+                None => Ok(map),
+            }
+        })
+    }
+
+    pub fn move_entity_to(&mut self, entity: Entity, target: &mut World) -> Result<Entity> {
+        let map = self.move_entities_to(&[entity], target)?;
+        Ok(map.map(entity))
+    }
+
+    pub fn move_entities_to(&mut self, entities: &[Entity], target: &mut World) -> Result<EntityMap> {
+        let map = self.copy_entities_to(entities, target)?;
+        for entity in entities {
+            self.delete_entity_by_id(entity.id)?;
+        }
+
+        Ok(map)
+    }
+
+    fn copy_components(&self, entities: &[Entity]) -> Result<Vec<(Entity, Vec<CopiedComponent>)>> {
+        entities
+            .iter()
+            .map(|entity| {
+                if !self.is_alive(*entity) {
+                    return Err(self.entities.entity_does_not_exist(entity.id));
+                }
+
+                let components = self
+                    .entities
+                    .clone_components(entity.id)?
+                    .into_iter()
+                    .map(|(id, component)| {
+                        let info = self.registry.get(&id);
+                        let name = info
+                            .map(|info| info.name().to_owned())
+                            .unwrap_or_else(|| self.entities.component_name(id));
+                        (id, name, component, info.and_then(|info| info.map_entities_fn()))
+                    })
+                    .collect();
+                Ok((*entity, components))
+            })
+            .collect()
+    }
+
+    // Checks every component is registered before creating anything, so a
+    // failed copy doesn't leave partial entities behind.
+    // Returns the references that were left pointing outside the copies.
+    fn insert_copies(&mut self, copies: Vec<(Entity, Vec<CopiedComponent>)>) -> Result<(EntityMap, Vec<Entity>)> {
+        for (_, components) in &copies {
+            for (id, name, component, _) in components {
+                if self.entities.get_bit_mask(id).is_none() {
+                    return Err(JellyEcsError::ComponentNotRegistered {
+                        entity: None,
+                        name: None,
+                        component: name.clone(),
+                    });
+                }
+                self.entities.check_dynamic_layout(*id, component, None)?;
+            }
+        }
+
+        let mut map = EntityMap::new();
+        let mut to_map = vec![];
+        for (source, components) in copies {
+            let mut builder = self.create_entity();
+            for (id, name, component, map_entities) in components {
+                if let Some(map_entities) = map_entities {
+                    to_map.push((component.clone(), map_entities));
+                }
+                builder.insert(id, name, component)?;
+            }
+            drop(builder);
+
+            let index = self.entities.inserting_into_index();
+            if let Some(entity) = self.entities.entity(index) {
+                map.insert(source, entity);
+            }
+        }
+
+        for (component, map_entities) in to_map {
+            map_entities(&mut *component.borrow_mut(), &map);
+        }
+
+        let unmapped = map.take_unmapped();
+        Ok((map, unmapped))
+    }
+
+    pub fn snapshot(&self) -> Result<WorldSnapshot> {
+        Ok(WorldSnapshot {
+            entities: self.entities.snapshot()?,
+            resources: self.resources.snapshot(),
+        })
+    }
+
+    // The journal's history no longer applies to the restored world.
+    pub fn restore(&mut self, snapshot: &WorldSnapshot) -> Result<()> {
+        self.entities.restore(&snapshot.entities)?;
+        self.resources.restore(&snapshot.resources);
+        if let Some(journal) = &mut self.journal {
+            journal.clear();
+        }
+        Ok(())
+    }
+
+    // A fingerprint of every live entity and its hashed components, and of the
+    // hashed resources. Worlds that went through the same steps hash the
+    // same, which is how replays check they haven't diverged.
+    pub fn hash(&self) -> Result<u64> {
+        let hashed = self.hashed_infos();
+        let mut hash = replay::FNV_OFFSET;
+        for entity in self.entities.iter() {
+            hash = replay::fnv1a(hash, &(entity.id as u64).to_le_bytes());
+            hash = replay::fnv1a(hash, &entity.generation.to_le_bytes());
+            for (info, bytes) in self.hashed_components(&hashed, entity.id)? {
+                hash = replay::fnv1a(hash, info.name().as_bytes());
+                hash = replay::fnv1a(hash, &bytes);
+            }
+        }
+        for (name, bytes) in self.resources.hashed() {
+            let bytes = bytes.map_err(|error| JellyEcsError::HashFailed {
+                entity: None,
+                name: None,
+                component: name.to_owned(),
+                error: error.to_string(),
+            })?;
+            hash = replay::fnv1a(hash, name.as_bytes());
+            hash = replay::fnv1a(hash, &bytes);
+        }
+
+        Ok(hash)
+    }
+
+    // Lists what differs between the two worlds' entities and hashed
+    // components, e.g. to find where a replay desynced.
+    pub fn diff(&self, other: &World) -> Result<WorldDiff> {
+        WorldDiff::new(self, other)
+    }
+
+    // The hashed component types, in name order.
+    pub(crate) fn hashed_infos(&self) -> Vec<&ComponentInfo> {
+        self.registry.iter().filter(|info| info.is_hashed()).collect()
+    }
+
+    // An entity's components out of `hashed`, with the bytes they hash as.
+    pub(crate) fn hashed_components<'a>(
+        &self,
+        hashed: &[&'a ComponentInfo],
+        index: usize,
+    ) -> Result<Vec<(&'a ComponentInfo, Vec<u8>)>> {
+        let mut components = vec![];
+        for info in hashed.iter().copied() {
+            if let Some(component) = self.entities.get_component(info.id(), index) {
+                let borrowed_component = component.borrow();
+                if let Some(bytes) = info.hash_bytes(&*borrowed_component) {
+                    let bytes = bytes
+                        .map_err(|error| JellyEcsError::HashFailed {
+                            entity: self.entities.entity(index),
+                            name: self.entities.name(index),
+                            component: info.short_name().to_owned(),
+                            error: error.to_string(),
+                        })?;
+                    components.push((info, bytes));
+                }
+            }
+        }
+
+        Ok(components)
+    }
+
+    pub fn dump(&self) -> WorldDump {
+        WorldDump::new(self)
+    }
+
+    // Times `scope` under `name` when a `Diagnostics` resource is present.
+    pub fn profile<R>(&mut self, name: &str, scope: impl FnOnce(&mut World) -> R) -> R {
+        if self.get_resource::<Diagnostics>().is_none() {
+            return scope(self);
+        }
+
+        let start = Instant::now();
+        let result = scope(self);
+        let duration = start.elapsed();
+        if let Some(diagnostics) = self.get_resource_mut::<Diagnostics>() {
+            diagnostics.record(name, start, duration);
+        }
+
+        result
+    }
+}
+
+impl Debug for World {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.dump(), f)
     }
 }
 
@@ -0,0 +1,347 @@
+use crate::errors::{JellyEcsError, Result};
+use serde::de::IgnoredAny;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+// A component value as the prefab spells it. RON values are kept as source
+// text and deserialized straight into the component, since converting them
+// to JSON loses enum variant and newtype struct names.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrefabValue {
+    Json(Value),
+    Ron(String),
+}
+
+// Component values keyed by component name, either the short name (`Health`)
+// or the full type path. Values use each component's serde representation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prefab {
+    components: BTreeMap<String, PrefabValue>,
+}
+
+impl Prefab {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_component(mut self, name: impl Into<String>, value: Value) -> Self {
+        self.components.insert(name.into(), PrefabValue::Json(value));
+        self
+    }
+
+    pub fn components(&self) -> impl Iterator<Item = (&str, &PrefabValue)> {
+        self.components.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    // Objects are merged field by field, anything else is replaced, so
+    // `{"Position": {"x": 1.0}}` only moves the spawn along one axis.
+    // Components the prefab doesn't have are added. A RON value merged with
+    // an object goes through JSON, so only struct-like values merge cleanly.
+    pub fn with_overrides(&self, overrides: &Value) -> Result<Self> {
+        let overrides = match overrides {
+            Value::Null => return Ok(self.clone()),
+            Value::Object(overrides) => overrides,
+            _ => {
+                return Err(JellyEcsError::InvalidPrefabOverrides(
+                    "prefab overrides must be an object of components".to_owned(),
+                ))
+            }
+        };
+
+        let mut prefab = self.clone();
+        for (name, value) in overrides {
+            let mut component = match prefab.components.remove(name) {
+                Some(PrefabValue::Json(component)) => component,
+                Some(PrefabValue::Ron(text)) if value.is_object() => {
+                    let component: ron::Value = ron::from_str(&text)
+                        .map_err(|error| JellyEcsError::InvalidPrefabOverrides(error.to_string()))?;
+                    serde_json::to_value(component)
+                        .map_err(|error| JellyEcsError::InvalidPrefabOverrides(error.to_string()))?
+                }
+                _ => Value::Null,
+            };
+            merge(&mut component, value);
+            prefab.components.insert(name.clone(), PrefabValue::Json(component));
+        }
+
+        Ok(prefab)
+    }
+}
+
+// Prefab definitions, stored as a resource for `World::spawn_prefab`. A file
+// maps prefab names to prefabs, e.g. in RON:
+//
+//     {
+//         "zombie": {
+//             "Position": (x: 0.0, y: 0.0),
+//             "Health": 100,
+//         },
+//     }
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Prefabs {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl Prefabs {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn from_json(text: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(text)
+            .map_err(|error| JellyEcsError::InvalidPrefabFile(error.to_string()))?;
+        Self::from_value(value)
+    }
+
+    pub fn from_ron(text: &str) -> Result<Self> {
+        let mut reader = RonReader { text };
+        let mut prefabs = Self::new();
+        reader.map("expected a map of prefab names to prefabs", |reader, name| {
+            let mut prefab = Prefab::new();
+            let expected = format!("prefab {} should be a map of component names to values", name);
+            reader.map(&expected, |reader, component| {
+                let value = reader.value()?;
+                prefab.components.insert(component, PrefabValue::Ron(value.to_owned()));
+                Ok(())
+            })?;
+            prefabs.insert(name, prefab);
+            Ok(())
+        })?;
+
+        reader.skip_whitespace()?;
+        if !reader.text.is_empty() {
+            return Err(JellyEcsError::InvalidPrefabFile("unexpected text after the prefabs".to_owned()));
+        }
+        Ok(prefabs)
+    }
+
+    // Picks the format from the extension; anything but `.ron` is read as JSON.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("ron") => Self::from_ron(&text),
+            _ => Self::from_json(&text),
+        }
+    }
+
+    fn from_value(value: Value) -> Result<Self> {
+        let definitions = match value {
+            Value::Object(definitions) => definitions,
+            _ => {
+                return Err(JellyEcsError::InvalidPrefabFile(
+                    "expected a map of prefab names to prefabs".to_owned(),
+                ))
+            }
+        };
+
+        let mut prefabs = Self::new();
+        for (name, components) in definitions {
+            match components {
+                Value::Object(components) => {
+                    let components = components
+                        .into_iter()
+                        .map(|(component, value)| (component, PrefabValue::Json(value)))
+                        .collect();
+                    prefabs.insert(name, Prefab { components })
+                }
+                _ => {
+                    return Err(JellyEcsError::InvalidPrefabFile(format!(
+                        "prefab {} should be a map of component names to values",
+                        name
+                    )))
+                }
+            }
+        }
+
+        Ok(prefabs)
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, prefab: Prefab) {
+        self.prefabs.insert(name.into(), prefab);
+    }
+
+    pub fn extend(&mut self, other: Prefabs) {
+        self.prefabs.extend(other.prefabs);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.prefabs.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+// Splits a RON prefab file into each component's source text, letting ron
+// itself find where values end and skip whitespace and comments.
+struct RonReader<'a> {
+    text: &'a str,
+}
+
+impl<'a> RonReader<'a> {
+    fn skip_whitespace(&mut self) -> Result<()> {
+        let deserializer = ron::Deserializer::from_str(self.text).map_err(invalid_ron)?;
+        let skipped = self.text.len() - deserializer.remainder().len();
+        self.text = &self.text[skipped..];
+        Ok(())
+    }
+
+    fn eat(&mut self, expected: char) -> Result<bool> {
+        self.skip_whitespace()?;
+        match self.text.strip_prefix(expected) {
+            Some(rest) => {
+                self.text = rest;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn value(&mut self) -> Result<&'a str> {
+        self.skip_whitespace()?;
+        let mut deserializer = ron::Deserializer::from_str(self.text).map_err(invalid_ron)?;
+        IgnoredAny::deserialize(&mut deserializer).map_err(|error| invalid_ron(deserializer.span_error(error)))?;
+        let length = self.text.len() - deserializer.remainder().len();
+        let (value, rest) = self.text.split_at(length);
+        self.text = rest;
+        Ok(value.trim_end())
+    }
+
+    // Calls `entry` with each key of a `{"key": value, ...}` map, for it to
+    // read the value.
+    fn map(&mut self, expected: &str, mut entry: impl FnMut(&mut Self, String) -> Result<()>) -> Result<()> {
+        let invalid = || JellyEcsError::InvalidPrefabFile(expected.to_owned());
+        if !self.eat('{')? {
+            return Err(invalid());
+        }
+
+        loop {
+            if self.eat('}')? {
+                return Ok(());
+            }
+            let key = ron::from_str(self.value()?).map_err(invalid_ron)?;
+            if !self.eat(':')? {
+                return Err(invalid());
+            }
+            entry(self, key)?;
+            if !self.eat(',')? {
+                return if self.eat('}')? { Ok(()) } else { Err(invalid()) };
+            }
+        }
+    }
+}
+
+fn invalid_ron(error: ron::error::SpannedError) -> JellyEcsError {
+    JellyEcsError::InvalidPrefabFile(error.to_string())
+}
+
+fn merge(base: &mut Value, overrides: &Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(key) {
+                    Some(base_value) => merge(base_value, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides.clone(),
+    }
+}
+
+// The closest candidate within a few typos, for "did you mean" hints.
+pub(crate) fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|candidate| (edit_distance(&name.to_lowercase(), &candidate.to_lowercase()), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate.to_owned())
+}
+
+fn edit_distance(first: &str, second: &str) -> usize {
+    let second: Vec<char> = second.chars().collect();
+    let mut previous: Vec<usize> = (0..=second.len()).collect();
+
+    for (row, first_char) in first.chars().enumerate() {
+        let mut current = vec![row + 1];
+        for (column, second_char) in second.iter().enumerate() {
+            let substitution = previous[column] + usize::from(first_char != *second_char);
+            current.push(substitution.min(previous[column + 1] + 1).min(current[column] + 1));
+        }
+        previous = current;
+    }
+
+    previous[second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn ron_prefabs_keep_each_component_as_written() -> Result<()> {
+        let prefabs = Prefabs::from_ron(
+            r#"{
+                // Comments and trailing commas are fine.
+                "zombie": {"Position": Position(x: 1.0, y: 2.0), "State": Chasing(target: 3), "Health": 100,},
+                "human": {},
+            }"#,
+        )?;
+
+        assert_eq!(prefabs.names(), vec!["human", "zombie"]);
+        let components: Vec<(&str, &PrefabValue)> = prefabs.get("zombie").unwrap().components().collect();
+        assert_eq!(
+            components,
+            vec![
+                ("Health", &PrefabValue::Ron("100".to_owned())),
+                ("Position", &PrefabValue::Ron("Position(x: 1.0, y: 2.0)".to_owned())),
+                ("State", &PrefabValue::Ron("Chasing(target: 3)".to_owned())),
+            ]
+        );
+
+        assert!(Prefabs::from_ron(r#"{"zombie": 3}"#).is_err());
+        assert!(Prefabs::from_ron(r#"{"zombie": {"Health": 100} "human": {}}"#).is_err());
+        assert!(Prefabs::from_ron(r#"{"zombie": {}} {}"#).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn overrides_are_merged_field_by_field() -> Result<()> {
+        let prefab = Prefab::new()
+            .with_component("Position", json!({"x": 1.0, "y": 2.0}))
+            .with_component("Health", json!(100));
+
+        let overridden = prefab.with_overrides(&json!({"Position": {"y": 5.0}, "Speed": 2.0}))?;
+        let components: Vec<(&str, &PrefabValue)> = overridden.components().collect();
+        assert_eq!(
+            components,
+            vec![
+                ("Health", &PrefabValue::Json(json!(100))),
+                ("Position", &PrefabValue::Json(json!({"x": 1.0, "y": 5.0}))),
+                ("Speed", &PrefabValue::Json(json!(2.0)))
+            ]
+        );
+        assert!(matches!(
+            prefab.with_overrides(&json!(3)),
+            Err(JellyEcsError::InvalidPrefabOverrides(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn suggest_close_names() {
+        let candidates = vec!["Health", "Position", "Velocity"];
+
+        assert_eq!(suggest("Helth", candidates.clone()), Some("Health".to_owned()));
+        assert_eq!(suggest("position", candidates.clone()), Some("Position".to_owned()));
+        assert_eq!(suggest("Inventory", candidates), None);
+    }
+}
@@ -0,0 +1,461 @@
+use crate::data::Data;
+use crate::entities::dynamic::DynamicComponent;
+use crate::entities::map_entities::{EntityMap, MapEntities};
+use crate::entities::{new_component, Component, ComponentId, Entity};
+use crate::errors::{JellyEcsError, Result};
+use crate::prefab::PrefabValue;
+use crate::relationship::Relationship;
+use bincode::Options;
+use ron::extensions::Extensions;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::any::{type_name, Any, TypeId};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::mem::size_of;
+
+pub type ReflectedField = (String, String);
+
+type ReflectFn = fn(&dyn Any) -> Vec<ReflectedField>;
+type DebugFn = fn(&dyn Any) -> String;
+type DeserializeFn = fn(&PrefabValue) -> std::result::Result<Component, String>;
+pub(crate) type MapEntitiesFn = fn(&mut dyn Any, &EntityMap);
+type EncodeFn = fn(&dyn Any) -> bincode::Result<Vec<u8>>;
+type TargetFn = fn(&dyn Any) -> Option<Entity>;
+type DecodeFn = fn(&[u8]) -> bincode::Result<Component>;
+
+pub trait Reflect {
+    fn fields(&self) -> Vec<ReflectedField>;
+}
+
+// Implements `Reflect` by formatting each listed field with `Debug`, e.g.
+// `impl_reflect!(Location { 0, 1 });` or `impl_reflect!(Velocity { x, y });`.
+#[macro_export]
+macro_rules! impl_reflect {
+    ($type:ty { $($field:tt),* $(,)? }) => {
+        impl $crate::registry::Reflect for $type {
+            fn fields(&self) -> Vec<$crate::registry::ReflectedField> {
+                vec![$((stringify!($field).to_owned(), format!("{:?}", self.$field))),*]
+            }
+        }
+    };
+}
+
+fn reflect<T: Reflect + Any>(value: &dyn Any) -> Vec<ReflectedField> {
+    value.downcast_ref::<T>().map(T::fields).unwrap_or_default()
+}
+
+// RON newtypes may be written bare, `"Health": 100` as well as `Health(100)`.
+fn deserialize<T: Data + DeserializeOwned>(value: &PrefabValue) -> std::result::Result<Component, String> {
+    match value {
+        PrefabValue::Json(value) => serde_json::from_value::<T>(value.clone()).map_err(|error| error.to_string()),
+        PrefabValue::Ron(text) => ron::from_str::<T>(text)
+            .or_else(|error| {
+                ron::Options::default()
+                    .with_default_extension(Extensions::UNWRAP_NEWTYPES)
+                    .from_str::<T>(text)
+                    .map_err(|_| error)
+            })
+            .map_err(|error| error.to_string()),
+    }
+    .map(new_component)
+}
+
+fn encode<T: Serialize + Any>(value: &dyn Any) -> bincode::Result<Vec<u8>> {
+    match value.downcast_ref::<T>() {
+        Some(value) => bincode::DefaultOptions::new().serialize(value),
+        None => Ok(vec![]),
+    }
+}
+
+fn decode<T: Data + DeserializeOwned>(bytes: &[u8]) -> bincode::Result<Component> {
+    bincode::DefaultOptions::new().deserialize::<T>(bytes).map(new_component)
+}
+
+fn target<T: Relationship>(value: &dyn Any) -> Option<Entity> {
+    value.downcast_ref::<T>().map(T::target)
+}
+
+fn map_target<T: Relationship>(value: &mut dyn Any, map: &EntityMap) {
+    if let Some(value) = value.downcast_mut::<T>() {
+        value.set_target(map.map(value.target()));
+    }
+}
+
+fn map_entities<T: MapEntities + Any>(value: &mut dyn Any, map: &EntityMap) {
+    if let Some(value) = value.downcast_mut::<T>() {
+        value.map_entities(map);
+    }
+}
+
+fn debug<T: Debug + Any>(value: &dyn Any) -> String {
+    value
+        .downcast_ref::<T>()
+        .map(|value| format!("{:?}", value))
+        .unwrap_or_default()
+}
+
+#[derive(Clone)]
+pub struct ComponentInfo {
+    id: ComponentId,
+    name: Cow<'static, str>,
+    short_name: String,
+    size: usize,
+    reflect: Option<ReflectFn>,
+    deserialize: Option<DeserializeFn>,
+    map_entities: Option<MapEntitiesFn>,
+    replicate: Option<(EncodeFn, DecodeFn)>,
+    hash: Option<EncodeFn>,
+    relationship: Option<TargetFn>,
+}
+
+impl ComponentInfo {
+    fn of<T: Any>() -> Self {
+        Self {
+            id: ComponentId::of::<T>(),
+            name: Cow::Borrowed(type_name::<T>()),
+            short_name: shorten_type_name(type_name::<T>()),
+            size: size_of::<T>(),
+            reflect: None,
+            deserialize: None,
+            map_entities: None,
+            replicate: None,
+            hash: None,
+            relationship: None,
+        }
+    }
+
+    fn dynamic(id: ComponentId, name: &str) -> Self {
+        Self {
+            id,
+            name: Cow::Owned(name.to_owned()),
+            short_name: name.to_owned(),
+            size: size_of::<DynamicComponent>(),
+            reflect: Some(reflect::<DynamicComponent>),
+            deserialize: None,
+            map_entities: None,
+            replicate: None,
+            hash: None,
+            relationship: None,
+        }
+    }
+
+    pub fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn short_name(&self) -> &str {
+        &self.short_name
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_reflected(&self) -> bool {
+        self.reflect.is_some()
+    }
+
+    pub fn reflect(&self, value: &dyn Any) -> Option<Vec<ReflectedField>> {
+        self.reflect.map(|reflect| reflect(value))
+    }
+
+    pub fn is_deserializable(&self) -> bool {
+        self.deserialize.is_some()
+    }
+
+    pub fn deserialize(&self, value: &PrefabValue) -> Option<std::result::Result<Component, String>> {
+        self.deserialize.map(|deserialize| deserialize(value))
+    }
+
+    pub fn maps_entities(&self) -> bool {
+        self.map_entities.is_some()
+    }
+
+    pub(crate) fn map_entities_fn(&self) -> Option<MapEntitiesFn> {
+        self.map_entities
+    }
+
+    pub fn is_replicated(&self) -> bool {
+        self.replicate.is_some()
+    }
+
+    pub fn encode(&self, value: &dyn Any) -> Option<bincode::Result<Vec<u8>>> {
+        self.replicate.map(|(encode, _)| encode(value))
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> Option<bincode::Result<Component>> {
+        self.replicate.map(|(_, decode)| decode(bytes))
+    }
+
+    pub fn is_relationship(&self) -> bool {
+        self.relationship.is_some()
+    }
+
+    // The entity a relationship component points at.
+    pub fn target(&self, value: &dyn Any) -> Option<Entity> {
+        self.relationship.and_then(|target| target(value))
+    }
+
+    pub fn is_hashed(&self) -> bool {
+        self.hash.is_some()
+    }
+
+    // The bytes that go into `World::hash` for this component.
+    pub fn hash_bytes(&self, value: &dyn Any) -> Option<bincode::Result<Vec<u8>>> {
+        self.hash.map(|hash| hash(value))
+    }
+}
+
+impl Debug for ComponentInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentInfo")
+            .field("name", &self.name)
+            .field("size", &self.size)
+            .field("reflected", &self.is_reflected())
+            .field("deserializable", &self.is_deserializable())
+            .field("replicated", &self.is_replicated())
+            .field("hashed", &self.is_hashed())
+            .field("relationship", &self.is_relationship())
+            .finish()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct TypeRegistry {
+    components: HashMap<ComponentId, ComponentInfo>,
+    debug: HashMap<TypeId, DebugFn>,
+}
+
+impl TypeRegistry {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn register<T: Any>(&mut self) -> &mut ComponentInfo {
+        self.components
+            .entry(ComponentId::of::<T>())
+            .or_insert_with(ComponentInfo::of::<T>)
+    }
+
+    // Works for resources as well as components.
+    pub fn register_debug<T: Debug + Any>(&mut self) {
+        self.debug.insert(TypeId::of::<T>(), debug::<T>);
+    }
+
+    pub fn register_dynamic(&mut self, id: ComponentId, name: &str) {
+        self.components.insert(id, ComponentInfo::dynamic(id, name));
+    }
+
+    pub fn register_reflect<T: Reflect + Any>(&mut self) {
+        self.register::<T>().reflect = Some(reflect::<T>);
+    }
+
+    pub fn register_deserialize<T: Data + DeserializeOwned>(&mut self) {
+        self.register::<T>().deserialize = Some(deserialize::<T>);
+    }
+
+    pub fn register_map_entities<T: MapEntities + Any>(&mut self) {
+        self.register::<T>().map_entities = Some(map_entities::<T>);
+    }
+
+    pub fn register_replicate<T: Data + Serialize + DeserializeOwned>(&mut self) {
+        self.register::<T>().replicate = Some((encode::<T>, decode::<T>));
+    }
+
+    // Relationship targets are remapped like `MapEntities` components when
+    // entities are cloned or copied.
+    pub fn register_relationship<T: Relationship>(&mut self) {
+        let info = self.register::<T>();
+        info.relationship = Some(target::<T>);
+        info.map_entities = Some(map_target::<T>);
+    }
+
+    pub fn register_hash<T: Serialize + Any>(&mut self) {
+        self.register::<T>().hash = Some(encode::<T>);
+    }
+
+    pub fn get(&self, id: &ComponentId) -> Option<&ComponentInfo> {
+        self.components.get(id)
+    }
+
+    // Takes a full type name or a short one. A short name shared by several
+    // types, say `a::Health` and `b::Health`, is an error rather than a guess.
+    pub fn get_by_name(&self, name: &str) -> Result<Option<&ComponentInfo>> {
+        let mut matches: Vec<&ComponentInfo> = self.components.values().filter(|info| info.name == name).collect();
+        if matches.is_empty() {
+            matches = self.components.values().filter(|info| info.short_name == name).collect();
+        }
+
+        match matches.len() {
+            0 | 1 => Ok(matches.pop()),
+            _ => {
+                let mut candidates: Vec<String> = matches.iter().map(|info| info.name().to_owned()).collect();
+                candidates.sort_unstable();
+                Err(JellyEcsError::AmbiguousComponentName {
+                    component: name.to_owned(),
+                    candidates,
+                })
+            }
+        }
+    }
+
+    pub fn name_of(&self, id: &ComponentId) -> Option<&str> {
+        self.get(id).map(ComponentInfo::name)
+    }
+
+    pub fn format_debug(&self, type_id: &TypeId, value: &dyn Any) -> Option<String> {
+        self.debug.get(type_id).map(|debug| debug(value))
+    }
+
+    // Prefers the registered `Debug` output and falls back on reflection.
+    pub fn describe(&self, id: &ComponentId, value: &dyn Any) -> Option<String> {
+        if let ComponentId::Type(type_id) = id {
+            if let Some(description) = self.format_debug(type_id, value) {
+                return Some(description);
+            }
+        }
+
+        let info = self.get(id)?;
+        let fields = info.reflect(value)?;
+        let fields: Vec<String> = fields
+            .into_iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+
+        Some(format!("{} {{ {} }}", info.short_name, fields.join(", ")))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ComponentInfo> {
+        let mut components: Vec<&ComponentInfo> = self.components.values().collect();
+        components.sort_by(|first, second| first.name.cmp(&second.name));
+        components.into_iter()
+    }
+}
+
+// Drops the module path from every type in a name, so
+// `alloc::vec::Vec<my_game::Location>` becomes `Vec<Location>`.
+pub fn shorten_type_name(name: &str) -> String {
+    let mut short_name = String::with_capacity(name.len());
+    let mut segment_start = 0;
+    for (index, character) in name.char_indices() {
+        if "<>,;()[]&* ".contains(character) {
+            short_name.push_str(last_path_segment(&name[segment_start..index]));
+            short_name.push(character);
+            segment_start = index + character.len_utf8();
+        }
+    }
+    short_name.push_str(last_path_segment(&name[segment_start..]));
+    short_name
+}
+
+fn last_path_segment(path: &str) -> &str {
+    path.rsplit("::").next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_component_info() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Location>();
+
+        let info = registry.get(&ComponentId::of::<Location>()).unwrap();
+        assert_eq!(info.name(), type_name::<Location>());
+        assert_eq!(info.short_name(), "Location");
+        assert_eq!(info.size(), 8);
+        assert!(!info.is_reflected());
+    }
+
+    #[test]
+    fn find_component_by_name() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Location>();
+        registry.register::<u32>();
+
+        assert_eq!(
+            registry.get_by_name("Location").unwrap().unwrap().id(),
+            ComponentId::of::<Location>()
+        );
+        assert_eq!(
+            registry.get_by_name(type_name::<Location>()).unwrap().unwrap().id(),
+            ComponentId::of::<Location>()
+        );
+        assert!(registry.get_by_name("Size").unwrap().is_none());
+
+        let names: Vec<&str> = registry.iter().map(ComponentInfo::short_name).collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"u32"));
+    }
+
+    #[test]
+    fn shared_short_names_are_ambiguous() {
+        let mut registry = TypeRegistry::new();
+        registry.register::<Location>();
+        registry.register::<other::Location>();
+
+        let error = registry.get_by_name("Location").err().unwrap();
+        assert!(matches!(error, JellyEcsError::AmbiguousComponentName { ref candidates, .. } if candidates.len() == 2));
+        assert_eq!(
+            registry.get_by_name(type_name::<other::Location>()).unwrap().unwrap().id(),
+            ComponentId::of::<other::Location>()
+        );
+    }
+
+    mod other {
+        pub struct Location;
+    }
+
+    #[test]
+    fn reflect_registered_component() {
+        let mut registry = TypeRegistry::new();
+        registry.register_reflect::<Location>();
+
+        let info = registry.get(&ComponentId::of::<Location>()).unwrap();
+        let fields = info.reflect(&Location(1.0, 2.5)).unwrap();
+        assert_eq!(
+            fields,
+            vec![
+                ("0".to_owned(), "1.0".to_owned()),
+                ("1".to_owned(), "2.5".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn describe_component_values() {
+        let mut registry = TypeRegistry::new();
+        registry.register_reflect::<Location>();
+        let id = ComponentId::of::<Location>();
+        assert_eq!(
+            registry.describe(&id, &Location(1.0, 2.0)).unwrap(),
+            "Location { 0: 1.0, 1: 2.0 }"
+        );
+
+        registry.register_debug::<Location>();
+        assert_eq!(
+            registry.describe(&id, &Location(1.0, 2.0)).unwrap(),
+            "Location(1.0, 2.0)"
+        );
+        assert!(registry.describe(&ComponentId::of::<u32>(), &1_u32).is_none());
+    }
+
+    #[test]
+    fn shorten_generic_type_names() {
+        assert_eq!(shorten_type_name("alloc::vec::Vec<my_game::Location>"), "Vec<Location>");
+        assert_eq!(
+            shorten_type_name("std::collections::HashMap<u32, (a::B, &c::D)>"),
+            "HashMap<u32, (B, &D)>"
+        );
+    }
+
+    #[derive(Debug)]
+    struct Location(pub f32, pub f32);
+
+    crate::impl_reflect!(Location { 0, 1 });
+}
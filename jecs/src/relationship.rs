@@ -0,0 +1,36 @@
+use crate::data::Data;
+use crate::entities::Entity;
+use serde::{Deserialize, Serialize};
+
+// A component that points at another entity. Once registered with
+// `World::register_relationship`, despawning the target removes the
+// component from every entity pointing at it and sends a
+// `RelationshipRemoved` event for each, and `World::sources` answers who
+// points at a given entity. Both read the targets off the components, so
+// retargeting through a query is picked up too.
+pub trait Relationship: Data {
+    fn target(&self) -> Entity;
+    fn set_target(&mut self, target: Entity);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Targets(pub Entity);
+
+impl Relationship for Targets {
+    fn target(&self) -> Entity {
+        self.0
+    }
+
+    fn set_target(&mut self, target: Entity) {
+        self.0 = target;
+    }
+}
+
+// `relationship` is the short type name of the removed component. A rolled
+// back transaction takes the event back, undoing the despawn later doesn't.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationshipRemoved {
+    pub source: Entity,
+    pub target: Entity,
+    pub relationship: String,
+}
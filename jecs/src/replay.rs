@@ -0,0 +1,320 @@
+use crate::errors::{JellyEcsError, Result};
+use crate::registry::shorten_type_name;
+use crate::rng::Rng;
+use crate::World;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::type_name;
+use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes
+        .iter()
+        .fold(hash, |hash, byte| (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME))
+}
+
+fn replay_error(error: impl ToString) -> JellyEcsError {
+    JellyEcsError::InvalidReplay(error.to_string())
+}
+
+// A mutation that comes from outside the schedule, such as player input.
+// Commands sent with `App::send_command` are applied at the start of the next
+// update, which is what lets a replay feed them back on the same tick.
+pub trait Command: Serialize + DeserializeOwned + 'static {
+    fn apply(self, world: &mut World) -> Result<()>;
+}
+
+type ApplyFn = fn(&mut World, &[u8]) -> Result<()>;
+
+fn apply<C: Command>(world: &mut World, bytes: &[u8]) -> Result<()> {
+    let command: C = bincode::DefaultOptions::new()
+        .deserialize(bytes)
+        .map_err(replay_error)?;
+    command.apply(world)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RecordedCommand {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReplayTick {
+    delta: Duration,
+    commands: Vec<RecordedCommand>,
+    hash: u64,
+}
+
+impl ReplayTick {
+    // The unscaled frame time, see `Time::raw_delta`.
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    pub fn commands(&self) -> Vec<&str> {
+        self.commands.iter().map(|command| command.name.as_str()).collect()
+    }
+
+    // The world hash at the end of the tick.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+// Everything needed to reproduce a run: the `Rng` seed, and for every tick
+// its frame time, the commands applied and the resulting world hash.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    seed: u64,
+    ticks: Vec<ReplayTick>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, ticks: vec![] }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn ticks(&self) -> &[ReplayTick] {
+        &self.ticks
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        bincode::DefaultOptions::new().serialize(self).map_err(replay_error)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::DefaultOptions::new().deserialize(bytes).map_err(replay_error)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[derive(Debug, Default)]
+enum Mode {
+    #[default]
+    Live,
+    Recording(Replay),
+    Playing { replay: Replay, tick: usize },
+}
+
+// Queued commands and the replay being recorded or played, owned by `App`.
+#[derive(Default)]
+pub(crate) struct CommandQueue {
+    commands: HashMap<String, ApplyFn>,
+    queue: Vec<RecordedCommand>,
+    mode: Mode,
+}
+
+impl CommandQueue {
+    pub(crate) fn register<C: Command>(&mut self) {
+        self.commands.insert(command_name::<C>(), apply::<C>);
+    }
+
+    pub(crate) fn send<C: Command>(&mut self, command: C) -> Result<()> {
+        let name = command_name::<C>();
+        if !self.commands.contains_key(&name) {
+            return Err(JellyEcsError::CommandNotRegistered(name));
+        }
+
+        // Live input has no say while a replay is playing.
+        if self.is_playing() {
+            return Ok(());
+        }
+
+        let bytes = bincode::DefaultOptions::new()
+            .serialize(&command)
+            .map_err(replay_error)?;
+        self.queue.push(RecordedCommand { name, bytes });
+        Ok(())
+    }
+
+    pub(crate) fn start_recording(&mut self, world: &mut World, seed: u64) {
+        world.add_resource(Rng::seeded(seed));
+        world.register_hashed_resource::<Rng>();
+        self.mode = Mode::Recording(Replay::new(seed));
+    }
+
+    pub(crate) fn stop_recording(&mut self) -> Option<Replay> {
+        match std::mem::take(&mut self.mode) {
+            Mode::Recording(replay) => Some(replay),
+            mode => {
+                self.mode = mode;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn play(&mut self, world: &mut World, replay: Replay) {
+        world.add_resource(Rng::seeded(replay.seed));
+        world.register_hashed_resource::<Rng>();
+        self.queue.clear();
+        self.mode = if replay.is_empty() {
+            Mode::Live
+        } else {
+            Mode::Playing { replay, tick: 0 }
+        };
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        matches!(self.mode, Mode::Recording(_))
+    }
+
+    pub(crate) fn is_playing(&self) -> bool {
+        matches!(self.mode, Mode::Playing { .. })
+    }
+
+    // The frame time a playing replay dictates for the coming tick.
+    pub(crate) fn playback_delta(&self) -> Option<Duration> {
+        match &self.mode {
+            Mode::Playing { replay, tick } => Some(replay.ticks[*tick].delta),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn apply_tick(&mut self, world: &mut World, delta: Duration) -> Result<()> {
+        let commands = match &mut self.mode {
+            Mode::Live => std::mem::take(&mut self.queue),
+            Mode::Recording(replay) => {
+                let commands = std::mem::take(&mut self.queue);
+                replay.ticks.push(ReplayTick {
+                    delta,
+                    commands: commands.clone(),
+                    hash: 0,
+                });
+                commands
+            }
+            Mode::Playing { replay, tick } => replay.ticks[*tick].commands.clone(),
+        };
+
+        for command in commands {
+            let apply = *self
+                .commands
+                .get(&command.name)
+                .ok_or_else(|| JellyEcsError::CommandNotRegistered(command.name.clone()))?;
+            apply(world, &command.bytes)?;
+        }
+
+        Ok(())
+    }
+
+    // Records the tick's hash, or checks it against the replay. Playback
+    // stops at the first divergence and once the replay runs out.
+    pub(crate) fn finish_tick(&mut self, world: &World) -> Result<()> {
+        match &mut self.mode {
+            Mode::Live => {}
+            Mode::Recording(replay) => {
+                if let Some(recorded) = replay.ticks.last_mut() {
+                    recorded.hash = world.hash()?;
+                }
+            }
+            Mode::Playing { replay, tick } => {
+                let expected = replay.ticks[*tick].hash;
+                let actual = world.hash()?;
+                if expected != actual {
+                    let diverged = *tick;
+                    self.mode = Mode::Live;
+                    return Err(JellyEcsError::ReplayDiverged(diverged, expected, actual));
+                }
+
+                *tick += 1;
+                if *tick == replay.ticks.len() {
+                    self.mode = Mode::Live;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Debug for CommandQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut commands: Vec<&String> = self.commands.keys().collect();
+        commands.sort_unstable();
+        f.debug_struct("CommandQueue")
+            .field("commands", &commands)
+            .field("queued", &self.queue.len())
+            .field("mode", &self.mode)
+            .finish()
+    }
+}
+
+fn command_name<C: Command>() -> String {
+    shorten_type_name(type_name::<C>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_reference_values() {
+        assert_eq!(fnv1a(FNV_OFFSET, b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(FNV_OFFSET, b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(FNV_OFFSET, b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn replays_round_trip_through_bytes() -> Result<()> {
+        let mut queue = CommandQueue::default();
+        let mut world = World::new();
+        queue.register::<Spawn>();
+        queue.start_recording(&mut world, 9);
+
+        queue.send(Spawn(3))?;
+        queue.apply_tick(&mut world, Duration::from_millis(16))?;
+        queue.finish_tick(&world)?;
+
+        let replay = queue.stop_recording().unwrap();
+        assert_eq!(replay.ticks()[0].commands(), vec!["Spawn"]);
+        assert_eq!(Replay::from_bytes(&replay.to_bytes()?)?, replay);
+        assert!(Replay::from_bytes(&[0xff]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn unregistered_commands_are_rejected() {
+        let mut queue = CommandQueue::default();
+        assert!(queue.send(Spawn(1)).is_err());
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Spawn(u32);
+
+    impl Command for Spawn {
+        fn apply(self, world: &mut World) -> Result<()> {
+            for _ in 0..self.0 {
+                world.create_entity();
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,551 @@
+use crate::entities::map_entities::EntityMap;
+use crate::entities::{ComponentId, Entity};
+use crate::errors::{JellyEcsError, Result};
+use crate::registry::ComponentInfo;
+use crate::World;
+use bincode::Options;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+pub type ClientId = usize;
+
+type ComponentBytes = BTreeMap<String, Vec<u8>>;
+
+// The largest encoded delta that is sent or accepted, so a corrupt or
+// hostile peer can't make the reader allocate arbitrary amounts.
+pub const MAX_DELTA_BYTES: u32 = 16 * 1024 * 1024;
+
+fn replication_error(error: impl ToString) -> JellyEcsError {
+    JellyEcsError::ReplicationFailed(error.to_string())
+}
+
+fn delta_options() -> impl Options {
+    bincode::DefaultOptions::new().with_limit(u64::from(MAX_DELTA_BYTES))
+}
+
+// Marks the client's copies of server entities, which keeps them alive
+// without any replicated components. On the server it replicates entities
+// that have none of their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Replicated;
+
+// The encoded replicated components of every entity at one tick. Entities
+// without any replicated component aren't part of it unless they are marked
+// `Replicated`.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ReplicationState {
+    entities: BTreeMap<Entity, ComponentBytes>,
+}
+
+impl ReplicationState {
+    fn capture(world: &World) -> Result<Self> {
+        let replicated: Vec<&ComponentInfo> = world
+            .registry
+            .iter()
+            .filter(|info| info.is_replicated())
+            .collect();
+        // Components go over the wire by short name, which has to be unique
+        // among the replicated ones.
+        let mut names = HashSet::new();
+        for info in &replicated {
+            if !names.insert(info.short_name()) {
+                return Err(ambiguous_name(&replicated, info.short_name()));
+            }
+        }
+
+        let mut entities = BTreeMap::new();
+        for entity in world.entities.iter() {
+            let mut components = BTreeMap::new();
+            for info in &replicated {
+                if let Some(component) = world.entities.get_component(info.id(), entity.id) {
+                    let borrowed_component = component.borrow();
+                    if let Some(bytes) = info.encode(&*borrowed_component) {
+                        components.insert(info.short_name().to_owned(), bytes.map_err(replication_error)?);
+                    }
+                }
+            }
+
+            if !components.is_empty() || world.entities.has_component(ComponentId::of::<Replicated>(), entity.id) {
+                entities.insert(entity, components);
+            }
+        }
+
+        Ok(Self { entities })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct EntityDelta {
+    entity: Entity,
+    components: Vec<(u16, Vec<u8>)>,
+}
+
+// What changed between a client's acknowledged tick and the server's latest
+// one. Entities are the server's handles and the short names of components
+// are stored once in a table that the rest of the delta indexes into. A delta
+// without a baseline carries the full state.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldDelta {
+    tick: u64,
+    baseline: Option<u64>,
+    names: Vec<String>,
+    spawned: Vec<EntityDelta>,
+    changed: Vec<EntityDelta>,
+    removed: Vec<(Entity, u16)>,
+    despawned: Vec<Entity>,
+}
+
+impl WorldDelta {
+    fn between(baseline: Option<(u64, &ReplicationState)>, current: (u64, &ReplicationState)) -> Result<Self> {
+        let empty = ReplicationState::default();
+        let (baseline_tick, baseline) = match baseline {
+            Some((tick, state)) => (Some(tick), state),
+            None => (None, &empty),
+        };
+        let (tick, current) = current;
+        let mut delta = Self {
+            tick,
+            baseline: baseline_tick,
+            ..Self::default()
+        };
+
+        for (entity, components) in &current.entities {
+            match baseline.entities.get(entity) {
+                None => {
+                    let components = delta.encode_components(components.iter())?;
+                    delta.spawned.push(EntityDelta { entity: *entity, components });
+                }
+                Some(old_components) => {
+                    let changed = components
+                        .iter()
+                        .filter(|(name, bytes)| old_components.get(*name) != Some(*bytes));
+                    let changed = delta.encode_components(changed)?;
+                    if !changed.is_empty() {
+                        delta.changed.push(EntityDelta { entity: *entity, components: changed });
+                    }
+
+                    for name in old_components.keys().filter(|name| !components.contains_key(*name)) {
+                        let index = delta.name_index(name)?;
+                        delta.removed.push((*entity, index));
+                    }
+                }
+            }
+        }
+
+        delta.despawned = baseline
+            .entities
+            .keys()
+            .filter(|entity| !current.entities.contains_key(entity))
+            .copied()
+            .collect();
+
+        Ok(delta)
+    }
+
+    fn encode_components<'a>(
+        &mut self,
+        components: impl Iterator<Item = (&'a String, &'a Vec<u8>)>,
+    ) -> Result<Vec<(u16, Vec<u8>)>> {
+        components
+            .map(|(name, bytes)| Ok((self.name_index(name)?, bytes.clone())))
+            .collect()
+    }
+
+    fn name_index(&mut self, name: &str) -> Result<u16> {
+        let index = match self.names.iter().position(|known| known == name) {
+            Some(index) => index,
+            None => {
+                self.names.push(name.to_owned());
+                self.names.len() - 1
+            }
+        };
+        u16::try_from(index).map_err(|_| replication_error("too many replicated component types"))
+    }
+
+    fn name(&self, index: u16) -> Result<&str> {
+        self.names
+            .get(index as usize)
+            .map(String::as_str)
+            .ok_or_else(|| replication_error(format!("component index {} is out of range", index)))
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn baseline(&self) -> Option<u64> {
+        self.baseline
+    }
+
+    pub fn spawned(&self) -> Vec<Entity> {
+        self.spawned.iter().map(|spawned| spawned.entity).collect()
+    }
+
+    pub fn despawned(&self) -> &[Entity] {
+        &self.despawned
+    }
+
+    pub fn changed(&self) -> Vec<Entity> {
+        self.changed.iter().map(|changed| changed.entity).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty() && self.changed.is_empty() && self.removed.is_empty() && self.despawned.is_empty()
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        delta_options().serialize(self).map_err(replication_error)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        delta_options().deserialize(bytes).map_err(replication_error)
+    }
+
+    // Frames the delta with its length so several can share a stream.
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        let bytes = self.to_bytes()?;
+        let len = u32::try_from(bytes.len()).map_err(replication_error)?;
+        writer.write_all(&len.to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        let mut len = [0; 4];
+        reader.read_exact(&mut len)?;
+        let len = u32::from_le_bytes(len);
+        if len > MAX_DELTA_BYTES {
+            return Err(replication_error(format!(
+                "delta of {} bytes is over the {} byte limit",
+                len, MAX_DELTA_BYTES
+            )));
+        }
+        let mut bytes = vec![0; len as usize];
+        reader.read_exact(&mut bytes)?;
+        Self::from_bytes(&bytes)
+    }
+}
+
+// Captures the world once per tick and hands every client a delta against
+// the last tick it acknowledged. States are kept until no client needs them
+// as a baseline any more.
+#[derive(Debug, Default)]
+pub struct ReplicationServer {
+    tick: u64,
+    history: BTreeMap<u64, ReplicationState>,
+    clients: HashMap<ClientId, Option<u64>>,
+    next_client: ClientId,
+}
+
+impl ReplicationServer {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add_client(&mut self) -> ClientId {
+        let client = self.next_client;
+        self.next_client += 1;
+        self.clients.insert(client, None);
+        client
+    }
+
+    pub fn remove_client(&mut self, client: ClientId) {
+        self.clients.remove(&client);
+        self.prune();
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn capture(&mut self, world: &World) -> Result<u64> {
+        let state = ReplicationState::capture(world)?;
+        self.tick += 1;
+        self.history.insert(self.tick, state);
+        self.prune();
+        Ok(self.tick)
+    }
+
+    pub fn delta_for(&self, client: ClientId) -> Result<WorldDelta> {
+        let acknowledged = self
+            .clients
+            .get(&client)
+            .ok_or_else(|| replication_error(format!("unknown client {}", client)))?;
+        let (tick, current) = self
+            .history
+            .iter()
+            .next_back()
+            .ok_or_else(|| replication_error("nothing was captured yet"))?;
+
+        let baseline = acknowledged.and_then(|acknowledged| {
+            self.history
+                .get(&acknowledged)
+                .map(|state| (acknowledged, state))
+        });
+        WorldDelta::between(baseline, (*tick, current))
+    }
+
+    pub fn acknowledge(&mut self, client: ClientId, tick: u64) {
+        if let Some(acknowledged) = self.clients.get_mut(&client) {
+            *acknowledged = Some(acknowledged.map_or(tick, |acknowledged| acknowledged.max(tick)));
+        }
+        self.prune();
+    }
+
+    fn prune(&mut self) {
+        let oldest_needed = self
+            .clients
+            .values()
+            .filter_map(|acknowledged| *acknowledged)
+            .min()
+            .unwrap_or(self.tick);
+        let latest = self.tick;
+        self.history
+            .retain(|tick, _| *tick >= oldest_needed || *tick == latest);
+    }
+}
+
+// Applies deltas to a local world, spawning its own entities for the
+// server's and keeping the mapping between the two.
+#[derive(Debug, Default)]
+pub struct ReplicationClient {
+    entities: EntityMap,
+    tick: Option<u64>,
+}
+
+impl ReplicationClient {
+    pub fn new() -> Self { Self::default() }
+
+    // The tick to acknowledge back to the server.
+    pub fn last_tick(&self) -> Option<u64> {
+        self.tick
+    }
+
+    pub fn local_entity(&self, server_entity: Entity) -> Option<Entity> {
+        self.entities.get(server_entity)
+    }
+
+    // The delta is applied as a transaction, so a bad delta leaves the world
+    // and the entity mapping as they were. Deltas older than the last applied
+    // one are ignored.
+    pub fn apply(&mut self, world: &mut World, delta: &WorldDelta) -> Result<()> {
+        if self.tick.is_some_and(|tick| delta.tick <= tick) {
+            return Ok(());
+        }
+        if delta.baseline.is_some() && delta.baseline != self.tick {
+            return Err(replication_error(format!(
+                "delta {} builds on tick {:?}, but the client is at tick {:?}",
+                delta.tick, delta.baseline, self.tick
+            )));
+        }
+
+        if world.entities.get_bit_mask(&ComponentId::of::<Replicated>()).is_none() {
+            world.register_cloneable_component::<Replicated>();
+        }
+
+        let mut entities = self.entities.clone();
+        world.transaction(|world| {
+            // A full state is diffed against the entities the client already
+            // has, so their local handles survive a resync.
+            if delta.baseline.is_none() {
+                let kept: HashSet<Entity> = delta.spawned.iter().map(|spawned| spawned.entity).collect();
+                for (server_entity, local) in entities.iter().collect::<Vec<_>>() {
+                    if !kept.contains(&server_entity) || !world.is_alive(local) {
+                        if world.is_alive(local) {
+                            world.delete_entity_by_id(local.id)?;
+                        }
+                        entities.remove(server_entity);
+                    }
+                }
+            }
+
+            let mut inserted = vec![];
+            for spawned in &delta.spawned {
+                let components = decode_components(world, delta, &spawned.components)?;
+                if let Some(local) = entities.get(spawned.entity) {
+                    let ids: Vec<ComponentId> = components.iter().map(|(id, ..)| *id).collect();
+                    let stale: Vec<ComponentId> = world
+                        .registry
+                        .iter()
+                        .filter(|info| info.is_replicated() && !ids.contains(&info.id()))
+                        .map(ComponentInfo::id)
+                        .filter(|id| world.entities.has_component(*id, local.id))
+                        .collect();
+                    for id in stale {
+                        world.delete_component_by_id(id, local.id)?;
+                    }
+                    for (id, name, component) in components {
+                        inserted.push((id, component.clone()));
+                        world.add_raw_component(id, name, component, local.id)?;
+                    }
+                    continue;
+                }
+
+                let mut builder = world.create_entity();
+                builder.with_component(Replicated)?;
+                for (id, name, component) in components {
+                    inserted.push((id, component.clone()));
+                    builder.insert(id, name, component)?;
+                }
+                entities.insert(spawned.entity, builder.entity());
+            }
+
+            for changed in &delta.changed {
+                let local = local_entity(&entities, changed.entity)?;
+                for (id, name, component) in decode_components(world, delta, &changed.components)? {
+                    inserted.push((id, component.clone()));
+                    world.add_raw_component(id, name, component, local.id)?;
+                }
+            }
+
+            for (server_entity, index) in &delta.removed {
+                let local = local_entity(&entities, *server_entity)?;
+                let id = replicated_info(world, delta.name(*index)?)?.id();
+                world.delete_component_by_id(id, local.id)?;
+            }
+
+            for server_entity in &delta.despawned {
+                let local = local_entity(&entities, *server_entity)?;
+                world.delete_entity_by_id(local.id)?;
+                entities.remove(*server_entity);
+            }
+
+            for (id, component) in inserted {
+                if let Some(map_entities) = world.registry.get(&id).and_then(ComponentInfo::map_entities_fn) {
+                    map_entities(&mut *component.borrow_mut(), &entities);
+                }
+            }
+
+            Ok(())
+        })?;
+
+        self.entities = entities;
+        self.tick = Some(delta.tick);
+        Ok(())
+    }
+}
+
+fn local_entity(entities: &EntityMap, server_entity: Entity) -> Result<Entity> {
+    entities
+        .get(server_entity)
+        .ok_or(JellyEcsError::UnknownServerEntity { entity: server_entity })
+}
+
+fn replicated_info<'a>(world: &'a World, name: &str) -> Result<&'a ComponentInfo> {
+    let replicated: Vec<&ComponentInfo> = world.registry.iter().filter(|info| info.is_replicated()).collect();
+    let mut matches = replicated.iter().filter(|info| info.short_name() == name);
+    match (matches.next(), matches.next()) {
+        (Some(info), None) => Ok(info),
+        (Some(_), Some(_)) => Err(ambiguous_name(&replicated, name)),
+        _ => Err(JellyEcsError::ComponentNotReplicated {
+            component: name.to_owned(),
+        }),
+    }
+}
+
+fn ambiguous_name(replicated: &[&ComponentInfo], name: &str) -> JellyEcsError {
+    let candidates = replicated
+        .iter()
+        .filter(|info| info.short_name() == name)
+        .map(|info| info.name().to_owned())
+        .collect();
+    JellyEcsError::AmbiguousComponentName {
+        component: name.to_owned(),
+        candidates,
+    }
+}
+
+fn decode_components(
+    world: &World,
+    delta: &WorldDelta,
+    components: &[(u16, Vec<u8>)],
+) -> Result<Vec<(ComponentId, String, crate::entities::Component)>> {
+    components
+        .iter()
+        .map(|(index, bytes)| {
+            let info = replicated_info(world, delta.name(*index)?)?;
+            let component = info
+                .decode(bytes)
+                .ok_or_else(|| JellyEcsError::ComponentNotReplicated {
+                    component: info.name().to_owned(),
+                })?
+                .map_err(replication_error)?;
+            Ok((info.id(), info.name().to_owned(), component))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_against_a_baseline_only_lists_changes() -> Result<()> {
+        let first = (0..3).map(|id| (entity(id), components(&[("Health", 100)]))).collect();
+        let first = ReplicationState { entities: first };
+        let mut second = first.clone();
+        second.entities.remove(&entity(0));
+        second.entities.insert(entity(1), components(&[("Health", 50), ("Speed", 1)]));
+        second.entities.insert(entity(3), components(&[("Speed", 2)]));
+
+        let delta = WorldDelta::between(Some((1, &first)), (2, &second))?;
+        assert_eq!(delta.baseline(), Some(1));
+        assert_eq!(delta.spawned(), vec![entity(3)]);
+        assert_eq!(delta.changed(), vec![entity(1)]);
+        assert_eq!(delta.despawned(), &[entity(0)]);
+        assert_eq!(delta.names, vec!["Health", "Speed"]);
+
+        let full = WorldDelta::between(None, (2, &second))?;
+        assert_eq!(full.spawned().len(), 3);
+        assert!(full.changed().is_empty() && full.despawned().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn deltas_round_trip_through_bytes() -> Result<()> {
+        let state = ReplicationState {
+            entities: vec![(entity(0), components(&[("Health", 100)]))].into_iter().collect(),
+        };
+        let delta = WorldDelta::between(None, (1, &state))?;
+
+        let mut stream = vec![];
+        delta.write_to(&mut stream)?;
+        delta.write_to(&mut stream)?;
+        let mut reader = &stream[..];
+        assert_eq!(WorldDelta::read_from(&mut reader)?, delta);
+        assert_eq!(WorldDelta::read_from(&mut reader)?, delta);
+        Ok(())
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected_before_reading() {
+        let mut stream = (MAX_DELTA_BYTES + 1).to_le_bytes().to_vec();
+        stream.extend_from_slice(&[0; 16]);
+        assert!(matches!(
+            WorldDelta::read_from(&mut &stream[..]),
+            Err(JellyEcsError::ReplicationFailed(_))
+        ));
+    }
+
+    #[test]
+    fn name_tables_past_u16_are_rejected() {
+        let mut delta = WorldDelta {
+            names: (0..=u16::MAX).map(|id| id.to_string()).collect(),
+            ..WorldDelta::default()
+        };
+        assert_eq!(delta.name_index("65535").ok(), Some(u16::MAX));
+        assert!(delta.name_index("overflow").is_err());
+    }
+
+    fn entity(id: usize) -> Entity {
+        Entity { id, generation: 0 }
+    }
+
+    fn components(values: &[(&str, u8)]) -> ComponentBytes {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), vec![*value]))
+            .collect()
+    }
+}
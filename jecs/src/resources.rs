@@ -1,30 +1,171 @@
-use std::any::{Any, TypeId};
+use crate::data::Data;
+use bincode::Options;
+use serde::Serialize;
+use std::any::{type_name, type_name_of_val, Any, TypeId};
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::mem;
+use std::thread::{self, ThreadId};
+
+#[cfg(not(feature = "sync"))]
+pub type Resource = Box<dyn Any>;
+#[cfg(feature = "sync")]
+pub type Resource = Box<dyn Any + Send + Sync>;
+
+type CloneFn = fn(&Resource) -> Resource;
+
+fn clone_resource<T: Data + Clone>(resource: &Resource) -> Resource {
+    Box::new(resource.downcast_ref::<T>().unwrap().clone())
+}
+
+type HashFn = fn(&dyn Any) -> bincode::Result<Vec<u8>>;
+
+fn hash_resource<T: Serialize + Any>(resource: &dyn Any) -> bincode::Result<Vec<u8>> {
+    bincode::DefaultOptions::new().serialize(resource.downcast_ref::<T>().unwrap())
+}
 
 #[derive(Default, Debug)]
 pub struct Resources {
-    data: HashMap<TypeId, Box<dyn Any>>,
+    data: HashMap<TypeId, Resource>,
+    names: HashMap<TypeId, &'static str>,
+    cloners: HashMap<TypeId, CloneFn>,
+    hashers: HashMap<TypeId, (&'static str, HashFn)>,
 }
 
 impl Resources {
     pub fn new() -> Self { Self::default() }
 
+    pub fn register_cloneable<T: Data + Clone>(&mut self) {
+        self.cloners.insert(TypeId::of::<T>(), clone_resource::<T>);
+    }
+
+    pub fn register_hashed<T: Data + Serialize>(&mut self) {
+        self.hashers.insert(TypeId::of::<T>(), (type_name::<T>(), hash_resource::<T>));
+    }
+
+    // The bytes of every hashed resource that is present, in name order.
+    pub(crate) fn hashed(&self) -> Vec<(&'static str, bincode::Result<Vec<u8>>)> {
+        let mut hashed: Vec<_> = self
+            .hashers
+            .iter()
+            .filter_map(|(type_id, (name, hash))| {
+                let resource: &dyn Any = &**self.data.get(type_id)?;
+                Some((*name, hash(resource)))
+            })
+            .collect();
+        hashed.sort_by_key(|(name, _)| *name);
+        hashed
+    }
+
+    pub fn add(&mut self, resource: impl Data) {
+        self.names.insert(resource.type_id(), type_name_of_val(&resource));
+        self.data.insert(resource.type_id(), Box::new(resource));
+    }
+
+    pub fn get_ref<T: Any>(&self) -> Option<&T> {
+        self.data.get(&TypeId::of::<T>())?.downcast_ref::<T>()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (TypeId, &'static str, &dyn Any)> {
+        self.data.iter().map(move |(type_id, resource)| {
+            let resource: &dyn Any = &**resource;
+            (*type_id, self.names[type_id], resource)
+        })
+    }
+
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.data.get_mut(&TypeId::of::<T>())?.downcast_mut::<T>()
+    }
+
+    pub fn remove<T: Any>(&mut self) {
+        self.data.remove(&TypeId::of::<T>());
+    }
+
+    // Only cloneable resources are captured; everything else is left untouched
+    // when the snapshot is restored.
+    pub fn snapshot(&self) -> ResourcesSnapshot {
+        let data = self
+            .cloners
+            .iter()
+            .filter_map(|(type_id, cloner)| {
+                let resource = self.data.get(type_id)?;
+                Some((*type_id, cloner(resource)))
+            })
+            .collect();
+
+        ResourcesSnapshot { data }
+    }
+
+    pub fn restore(&mut self, snapshot: &ResourcesSnapshot) {
+        for (type_id, cloner) in &self.cloners {
+            match snapshot.data.get(type_id) {
+                Some(resource) => self.data.insert(*type_id, cloner(resource)),
+                None => self.data.remove(type_id),
+            };
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ResourcesSnapshot {
+    data: HashMap<TypeId, Resource>,
+}
+
+// Resources that can't leave the thread that inserted them, such as graphics
+// handles. The store can travel along with a World, but touching it from any
+// other thread panics. Dropped on another thread, its data is leaked rather
+// than dropped there.
+#[derive(Default, Debug)]
+pub struct NonSendResources {
+    owner: Option<ThreadId>,
+    data: HashMap<TypeId, Box<dyn Any>>,
+}
+
+// Every access goes through `check_thread`, so the data never leaves its owner.
+unsafe impl Send for NonSendResources {}
+unsafe impl Sync for NonSendResources {}
+
+impl NonSendResources {
+    pub fn new() -> Self { Self::default() }
+
     pub fn add(&mut self, resource: impl Any) {
+        self.check_thread();
+        self.owner = Some(thread::current().id());
         self.data.insert(resource.type_id(), Box::new(resource));
     }
 
     pub fn get_ref<T: Any>(&self) -> Option<&T> {
+        self.check_thread();
         self.data.get(&TypeId::of::<T>())?.downcast_ref::<T>()
     }
 
     pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.check_thread();
         self.data.get_mut(&TypeId::of::<T>())?.downcast_mut::<T>()
     }
 
     pub fn remove<T: Any>(&mut self) {
+        self.check_thread();
         self.data.remove(&TypeId::of::<T>());
     }
+
+    fn is_owner_thread(&self) -> bool {
+        self.owner.map_or(true, |owner| owner == thread::current().id())
+    }
+
+    fn check_thread(&self) {
+        if !self.is_owner_thread() {
+            panic!("Attempted to access a non-send resource from a thread other than the one that inserted it");
+        }
+    }
+}
+
+impl Drop for NonSendResources {
+    fn drop(&mut self) {
+        if !self.is_owner_thread() {
+            mem::forget(mem::take(&mut self.data));
+        }
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +202,32 @@ mod tests {
         assert_eq!(health.0, 110);
     }
 
+    #[test]
+    fn iterate_over_resources() {
+        let resources = initialize_resources();
+        let listed: Vec<(TypeId, &str)> = resources
+            .iter()
+            .map(|(type_id, name, resource)| {
+                assert_eq!(resource.downcast_ref::<Health>().unwrap().0, 100);
+                (type_id, name)
+            })
+            .collect();
+        assert_eq!(listed, vec![(TypeId::of::<Health>(), std::any::type_name::<Health>())]);
+    }
+
+    #[test]
+    fn only_hashed_resources_are_hashed() {
+        let mut resources = initialize_resources();
+        resources.register_hashed::<Score>();
+        assert!(resources.hashed().is_empty());
+
+        resources.add(Score(3));
+        let hashed = resources.hashed();
+        assert_eq!(hashed.len(), 1);
+        assert_eq!(hashed[0].0, type_name::<Score>());
+        assert_eq!(hashed[0].1.as_ref().unwrap(), &vec![3]);
+    }
+
     #[test]
     fn remove_resource() {
         let mut resources = initialize_resources();
@@ -68,6 +235,69 @@ mod tests {
         assert!(!resources.data.contains_key(&TypeId::of::<Health>()));
     }
 
+    #[test]
+    fn non_send_resource() {
+        let mut resources = NonSendResources::new();
+        resources.add(std::rc::Rc::new(Health::new(100)));
+        resources.get_mut::<std::rc::Rc<Health>>().unwrap();
+        assert_eq!(resources.get_ref::<std::rc::Rc<Health>>().unwrap().0, 100);
+
+        resources.remove::<std::rc::Rc<Health>>();
+        assert!(resources.get_ref::<std::rc::Rc<Health>>().is_none());
+    }
+
+    #[test]
+    fn non_send_resources_panic_on_other_threads() {
+        let mut resources = NonSendResources::new();
+        resources.add(Health::new(100));
+
+        let result = thread::scope(|scope| {
+            scope.spawn(|| resources.get_ref::<Health>().is_some()).join()
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn non_send_resources_dropped_on_other_threads_are_leaked() {
+        let health = std::rc::Rc::new(Health::new(100));
+        let mut resources = NonSendResources::new();
+        resources.add(std::rc::Rc::clone(&health));
+
+        thread::scope(|scope| {
+            scope.spawn(|| drop(mem::take(&mut resources))).join().unwrap();
+        });
+        assert_eq!(std::rc::Rc::strong_count(&health), 2);
+    }
+
+    #[test]
+    fn snapshot_and_restore_cloneable_resources() {
+        let mut resources = initialize_resources();
+        resources.register_cloneable::<Score>();
+        resources.add(Score(1));
+        let snapshot = resources.snapshot();
+
+        resources.get_mut::<Score>().unwrap().0 = 2;
+        resources.get_mut::<Health>().unwrap().0 = 50;
+        resources.restore(&snapshot);
+        assert_eq!(resources.get_ref::<Score>().unwrap().0, 1);
+        assert_eq!(resources.get_ref::<Health>().unwrap().0, 50);
+
+        resources.remove::<Score>();
+        resources.restore(&snapshot);
+        assert_eq!(resources.get_ref::<Score>().unwrap().0, 1);
+    }
+
+    #[test]
+    fn restoring_removes_cloneable_resources_added_after_the_snapshot() {
+        let mut resources = initialize_resources();
+        resources.register_cloneable::<Score>();
+        let snapshot = resources.snapshot();
+
+        resources.add(Score(1));
+        resources.restore(&snapshot);
+        assert!(resources.get_ref::<Score>().is_none());
+    }
+
     fn initialize_resources() -> Resources {
         let mut resources = Resources::new();
         let health = Health::new(100);
@@ -79,6 +309,9 @@ mod tests {
     #[derive(Debug)]
     struct Health(pub u32);
 
+    #[derive(Debug, Clone, Serialize)]
+    struct Score(pub u32);
+
     impl Health {
         pub fn new(health: u32) -> Self {
             Self(health)
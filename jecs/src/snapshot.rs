@@ -0,0 +1,8 @@
+use crate::entities::EntitiesSnapshot;
+use crate::resources::ResourcesSnapshot;
+
+#[derive(Debug)]
+pub struct WorldSnapshot {
+    pub(crate) entities: EntitiesSnapshot,
+    pub(crate) resources: ResourcesSnapshot,
+}
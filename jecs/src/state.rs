@@ -0,0 +1,203 @@
+use crate::app::System;
+use crate::data::Data;
+use crate::errors::Result;
+use crate::World;
+use std::any::Any;
+use std::fmt::Debug;
+
+pub trait StateValue: Data + Clone + PartialEq + Debug {}
+
+impl<T: Data + Clone + PartialEq + Debug> StateValue for T {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct State<S: StateValue> {
+    current: S,
+}
+
+impl<S: StateValue> State<S> {
+    pub fn new(current: S) -> Self {
+        Self { current }
+    }
+
+    pub fn get(&self) -> &S {
+        &self.current
+    }
+}
+
+// Requests a transition; it is applied at the start of the next schedule run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NextState<S: StateValue> {
+    next: Option<S>,
+}
+
+impl<S: StateValue> Default for NextState<S> {
+    fn default() -> Self {
+        Self { next: None }
+    }
+}
+
+impl<S: StateValue> NextState<S> {
+    pub fn set(&mut self, next: S) {
+        self.next = Some(next);
+    }
+
+    pub fn pending(&self) -> Option<&S> {
+        self.next.as_ref()
+    }
+}
+
+pub fn in_state<S: StateValue>(state: S) -> impl Fn(&World) -> bool {
+    move |world| {
+        world
+            .get_resource::<State<S>>()
+            .is_some_and(|current| current.get() == &state)
+    }
+}
+
+pub(crate) trait Transitions {
+    fn apply(&mut self, world: &mut World) -> Result<()>;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+pub(crate) struct StateTransitions<S: StateValue> {
+    on_enter: Vec<(S, System)>,
+    on_exit: Vec<(S, System)>,
+    entered: Option<S>,
+}
+
+impl<S: StateValue> Default for StateTransitions<S> {
+    fn default() -> Self {
+        Self {
+            on_enter: vec![],
+            on_exit: vec![],
+            entered: None,
+        }
+    }
+}
+
+impl<S: StateValue> StateTransitions<S> {
+    pub(crate) fn add_on_enter(&mut self, state: S, system: System) {
+        self.on_enter.push((state, system));
+    }
+
+    pub(crate) fn add_on_exit(&mut self, state: S, system: System) {
+        self.on_exit.push((state, system));
+    }
+
+    fn run_matching(systems: &mut [(S, System)], state: &S, world: &mut World) -> Result<()> {
+        for (bound_state, system) in systems {
+            if bound_state == state {
+                system.run(world)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: StateValue> Transitions for StateTransitions<S> {
+    fn apply(&mut self, world: &mut World) -> Result<()> {
+        let current = match world.get_resource::<State<S>>() {
+            Some(state) => state.get().clone(),
+            None => return Ok(()),
+        };
+
+        if self.entered.is_none() {
+            Self::run_matching(&mut self.on_enter, &current, world)?;
+            self.entered = Some(current.clone());
+        }
+
+        let next = world
+            .get_resource_mut::<NextState<S>>()
+            .and_then(|next_state| next_state.next.take());
+        let next = match next {
+            Some(next) if next != current => next,
+            _ => return Ok(()),
+        };
+
+        Self::run_matching(&mut self.on_exit, &current, world)?;
+        world.add_resource(State::new(next.clone()));
+        Self::run_matching(&mut self.on_enter, &next, world)?;
+        self.entered = Some(next);
+
+        Ok(())
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transitions_run_exit_then_enter_systems() -> Result<()> {
+        let mut world = World::new();
+        world.add_resource(State::new(Mode::Menu));
+        world.add_resource(NextState::<Mode>::default());
+        world.add_resource(Log(vec![]));
+
+        let mut transitions = StateTransitions::<Mode>::default();
+        transitions.add_on_enter(Mode::Menu, log("enter menu"));
+        transitions.add_on_exit(Mode::Menu, log("exit menu"));
+        transitions.add_on_enter(Mode::Running, log("enter running"));
+
+        transitions.apply(&mut world)?;
+        transitions.apply(&mut world)?;
+        world.get_resource_mut::<NextState<Mode>>().unwrap().set(Mode::Running);
+        transitions.apply(&mut world)?;
+
+        assert_eq!(world.get_resource::<State<Mode>>().unwrap().get(), &Mode::Running);
+        assert!(world.get_resource::<NextState<Mode>>().unwrap().pending().is_none());
+        assert_eq!(
+            world.get_resource::<Log>().unwrap().0,
+            vec!["enter menu", "exit menu", "enter running"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn transition_to_the_current_state_is_ignored() -> Result<()> {
+        let mut world = World::new();
+        world.add_resource(State::new(Mode::Menu));
+        world.add_resource(NextState::<Mode>::default());
+        world.add_resource(Log(vec![]));
+
+        let mut transitions = StateTransitions::<Mode>::default();
+        transitions.add_on_exit(Mode::Menu, log("exit menu"));
+        transitions.apply(&mut world)?;
+        world.get_resource_mut::<NextState<Mode>>().unwrap().set(Mode::Menu);
+        transitions.apply(&mut world)?;
+
+        assert!(world.get_resource::<Log>().unwrap().0.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn in_state_condition() {
+        let mut world = World::new();
+        let running = in_state(Mode::Running);
+        assert!(!running(&world));
+
+        world.add_resource(State::new(Mode::Running));
+        assert!(running(&world));
+    }
+
+    fn log(message: &'static str) -> System {
+        System::new(message, move |world| {
+            world.get_resource_mut::<Log>().unwrap().0.push(message);
+            Ok(())
+        })
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Mode {
+        Menu,
+        Running,
+    }
+
+    struct Log(pub Vec<&'static str>);
+}
@@ -0,0 +1,322 @@
+use crate::app::{App, Plugin};
+use crate::data::Data;
+use crate::errors::{JellyEcsError, Result};
+use std::convert::TryFrom;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_FIXED_STEP: Duration = Duration::from_nanos(1_000_000_000 / 60);
+const DEFAULT_MAX_STEPS: u32 = 5;
+
+pub trait Clock: Data + Debug {
+    fn now(&self) -> Duration;
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+// Only moves when advanced by hand. Clones share the same reading, so a test
+// can keep one and hand the other to `Time::with_clock`.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self { Self::default() }
+
+    // Saturates at `u64::MAX` nanoseconds, some 584 years in.
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        let _ = self
+            .nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| Some(now.saturating_add(nanos)));
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+// Frame time, updated by `App::update` before the schedule runs. `delta` and
+// `elapsed` are scaled by `time_scale`; the first frame has a zero delta.
+#[derive(Debug)]
+pub struct Time {
+    clock: Box<dyn Clock>,
+    last: Option<Duration>,
+    raw_delta: Duration,
+    delta: Duration,
+    elapsed: Duration,
+    ticks: u64,
+    time_scale: f32,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::with_clock(SystemClock::default())
+    }
+}
+
+impl Time {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with_clock(clock: impl Clock) -> Self {
+        Self {
+            clock: Box::new(clock),
+            last: None,
+            raw_delta: Duration::ZERO,
+            delta: Duration::ZERO,
+            elapsed: Duration::ZERO,
+            ticks: 0,
+            time_scale: 1.0,
+        }
+    }
+
+    pub fn delta(&self) -> Duration {
+        self.delta
+    }
+
+    // The last delta before `time_scale` was applied.
+    pub fn raw_delta(&self) -> Duration {
+        self.raw_delta
+    }
+
+    pub fn delta_seconds(&self) -> f32 {
+        self.delta.as_secs_f32()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+
+    pub fn update(&mut self) {
+        let now = self.clock.now();
+        let raw_delta = self
+            .last
+            .map_or(Duration::ZERO, |last| now.saturating_sub(last));
+
+        self.last = Some(now);
+        self.tick(raw_delta);
+    }
+
+    // Moves time on by a given unscaled delta instead of reading the clock,
+    // which is how replays reproduce the recorded frame times.
+    pub fn advance(&mut self, raw_delta: Duration) {
+        self.last = Some(self.clock.now());
+        self.tick(raw_delta);
+    }
+
+    fn tick(&mut self, raw_delta: Duration) {
+        self.raw_delta = raw_delta;
+        self.delta = raw_delta.mul_f64(f64::from(self.time_scale));
+        self.elapsed += self.delta;
+        self.ticks += 1;
+    }
+}
+
+// Drives the fixed-timestep stage. Frame time is banked in an accumulator and
+// spent one `step` at a time; when more than `max_steps` are due in a single
+// frame the backlog is dropped rather than letting the simulation spiral.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedTime {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+    ticks: u64,
+}
+
+impl Default for FixedTime {
+    fn default() -> Self {
+        Self::new(DEFAULT_FIXED_STEP)
+    }
+}
+
+impl FixedTime {
+    pub fn new(step: Duration) -> Self {
+        assert!(!step.is_zero(), "The fixed timestep can't be zero");
+
+        Self {
+            step,
+            max_steps: DEFAULT_MAX_STEPS,
+            accumulator: Duration::ZERO,
+            ticks: 0,
+        }
+    }
+
+    // Zero, negative and NaN rates are rejected, as are rates so extreme the
+    // step rounds to nothing or doesn't fit a `Duration`.
+    pub fn from_hz(hz: f64) -> Result<Self> {
+        match Duration::try_from_secs_f64(1.0 / hz) {
+            Ok(step) if !step.is_zero() => Ok(Self::new(step)),
+            _ => Err(JellyEcsError::InvalidTickRate(hz)),
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    pub fn step_seconds(&self) -> f32 {
+        self.step.as_secs_f32()
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // How far the accumulator is into the next step, for interpolating renders.
+    pub fn overstep(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    pub(crate) fn steps_due(&mut self, delta: Duration) -> u32 {
+        self.accumulator = self.accumulator.saturating_add(delta);
+        let due = u32::try_from(self.accumulator.as_nanos() / self.step.as_nanos()).unwrap_or(u32::MAX);
+        let steps = due.min(self.max_steps);
+
+        self.accumulator -= self.step * steps;
+        if due > steps {
+            let leftover = self.accumulator.as_nanos() % self.step.as_nanos();
+            self.accumulator = Duration::from_nanos(u64::try_from(leftover).unwrap_or(u64::MAX));
+        }
+
+        steps
+    }
+
+    pub(crate) fn finish_step(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimePlugin {
+    pub fixed_step: Duration,
+    pub max_steps: u32,
+}
+
+impl Default for TimePlugin {
+    fn default() -> Self {
+        Self {
+            fixed_step: DEFAULT_FIXED_STEP,
+            max_steps: DEFAULT_MAX_STEPS,
+        }
+    }
+}
+
+impl Plugin for TimePlugin {
+    fn build(self, app: &mut App) {
+        app.insert_resource(Time::new())
+            .insert_resource(FixedTime::new(self.fixed_step).with_max_steps(self.max_steps));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_tracks_scaled_delta_and_elapsed() {
+        let clock = ManualClock::new();
+        let mut time = Time::with_clock(clock.clone());
+
+        time.update();
+        assert_eq!(time.delta(), Duration::ZERO);
+
+        clock.advance(Duration::from_millis(100));
+        time.update();
+        assert_eq!(time.delta(), Duration::from_millis(100));
+
+        time.set_time_scale(0.5);
+        clock.advance(Duration::from_millis(100));
+        time.update();
+        assert_eq!(time.delta(), Duration::from_millis(50));
+        assert_eq!(time.elapsed(), Duration::from_millis(150));
+        assert_eq!(time.ticks(), 3);
+    }
+
+    #[test]
+    fn fixed_time_banks_leftover_time() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10));
+
+        assert_eq!(fixed.steps_due(Duration::from_millis(25)), 2);
+        assert_eq!(fixed.accumulator(), Duration::from_millis(5));
+        assert_eq!(fixed.steps_due(Duration::from_millis(5)), 1);
+        assert_eq!(fixed.accumulator(), Duration::ZERO);
+    }
+
+    #[test]
+    fn fixed_time_drops_backlog_past_the_cap() {
+        let mut fixed = FixedTime::new(Duration::from_millis(10)).with_max_steps(3);
+
+        assert_eq!(fixed.steps_due(Duration::from_millis(1_004)), 3);
+        assert_eq!(fixed.accumulator(), Duration::from_millis(4));
+    }
+
+    #[test]
+    fn fixed_time_survives_huge_deltas() {
+        let mut fixed = FixedTime::new(Duration::from_nanos(1));
+
+        assert_eq!(fixed.steps_due(Duration::MAX), DEFAULT_MAX_STEPS);
+        assert_eq!(fixed.steps_due(Duration::MAX), DEFAULT_MAX_STEPS);
+        assert_eq!(fixed.accumulator(), Duration::ZERO);
+    }
+
+    #[test]
+    fn unusable_rates_are_rejected() {
+        assert_eq!(FixedTime::from_hz(50.0).unwrap().step(), Duration::from_millis(20));
+        for hz in [0.0, -60.0, f64::NAN, f64::INFINITY, 1e-300] {
+            assert!(matches!(FixedTime::from_hz(hz), Err(JellyEcsError::InvalidTickRate(_))));
+        }
+    }
+
+    #[test]
+    fn manual_clocks_saturate() {
+        let clock = ManualClock::new();
+        clock.advance(Duration::MAX);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(clock.now(), Duration::from_nanos(u64::MAX));
+    }
+}
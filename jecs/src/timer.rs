@@ -0,0 +1,294 @@
+use crate::app::{App, Plugin};
+use crate::entities::Entity;
+use crate::errors::Result;
+use crate::time::Time;
+use crate::World;
+use std::convert::TryFrom;
+use std::fmt::{self, Debug};
+use std::time::Duration;
+
+// Runs against the world once the timer finishes, e.g. to swap components on
+// the entity that owns it.
+pub type TimerAction = fn(&mut World, Entity) -> Result<()>;
+
+const DEFAULT_MAX_CATCH_UP: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+#[derive(Clone)]
+pub struct Timer {
+    duration: Duration,
+    elapsed: Duration,
+    mode: TimerMode,
+    just_finished: bool,
+    times_finished: u32,
+    max_catch_up: u32,
+    action: Option<TimerAction>,
+}
+
+impl Timer {
+    pub fn new(duration: Duration, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            elapsed: Duration::ZERO,
+            mode,
+            just_finished: false,
+            times_finished: 0,
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+            action: None,
+        }
+    }
+
+    pub fn from_seconds(seconds: f32, mode: TimerMode) -> Self {
+        Self::new(Duration::from_secs_f32(seconds), mode)
+    }
+
+    pub fn with_action(mut self, action: TimerAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    // Caps how many times a repeating timer goes off in one tick, like
+    // `FixedTime::with_max_steps`.
+    pub fn with_max_catch_up(mut self, max_catch_up: u32) -> Self {
+        self.max_catch_up = max_catch_up.max(1);
+        self
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.duration.saturating_sub(self.elapsed)
+    }
+
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn just_finished(&self) -> bool {
+        self.just_finished
+    }
+
+    pub fn times_finished(&self) -> u32 {
+        self.times_finished
+    }
+
+    pub fn max_catch_up(&self) -> u32 {
+        self.max_catch_up
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+        self.just_finished = false;
+    }
+
+    // Returns how many times the timer went off during `delta`. A repeating
+    // timer carries the overshoot into its next round, so it can go off more
+    // than once on a long frame, up to `max_catch_up` times with the rest of
+    // the backlog dropped; a one-shot timer stays finished.
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        if self.mode == TimerMode::Once && self.finished() {
+            self.just_finished = false;
+            return 0;
+        }
+
+        self.elapsed = self.elapsed.saturating_add(delta);
+        let fired = match self.mode {
+            TimerMode::Once if self.finished() => {
+                self.elapsed = self.duration;
+                1
+            }
+            TimerMode::Once => 0,
+            TimerMode::Repeating if self.duration.is_zero() => 1,
+            TimerMode::Repeating => {
+                // Computed on nanoseconds, since `duration * rounds` overflows
+                // after a long stall or with a tiny duration.
+                let elapsed = self.elapsed.as_nanos();
+                let duration = self.duration.as_nanos();
+                self.elapsed = nanos_to_duration(elapsed % duration);
+                u32::try_from(elapsed / duration)
+                    .unwrap_or(u32::MAX)
+                    .min(self.max_catch_up)
+            }
+        };
+
+        self.just_finished = fired > 0;
+        self.times_finished = self.times_finished.saturating_add(fired);
+        fired
+    }
+}
+
+fn nanos_to_duration(nanos: u128) -> Duration {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    Duration::new((nanos / NANOS_PER_SEC) as u64, (nanos % NANOS_PER_SEC) as u32)
+}
+
+impl Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("duration", &self.duration)
+            .field("elapsed", &self.elapsed)
+            .field("mode", &self.mode)
+            .field("times_finished", &self.times_finished)
+            .field("max_catch_up", &self.max_catch_up)
+            .field("action", &self.action.is_some())
+            .finish()
+    }
+}
+
+// The entity is despawned once its lifetime runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lifetime {
+    remaining: Duration,
+}
+
+impl Lifetime {
+    pub fn new(duration: Duration) -> Self {
+        Self { remaining: duration }
+    }
+
+    pub fn from_seconds(seconds: f32) -> Self {
+        Self::new(Duration::from_secs_f32(seconds))
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.remaining
+    }
+
+    fn tick(&mut self, delta: Duration) -> bool {
+        self.remaining = self.remaining.saturating_sub(delta);
+        self.remaining.is_zero()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimerFinished {
+    pub entity: Entity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LifetimeExpired {
+    pub entity: Entity,
+}
+
+// Ticks every `Timer` and `Lifetime` by the frame's delta. Finished timers send
+// a `TimerFinished` event and run their action once per firing; entities whose
+// lifetime ran out send a `LifetimeExpired` event and are despawned.
+pub fn tick_timers(world: &mut World) -> Result<()> {
+    let delta = world
+        .get_resource::<Time>()
+        .map_or(Duration::ZERO, Time::delta);
+
+    let mut finished = vec![];
+    world.query().for_each::<(Timer,), _>(|index, (timer,)| {
+        let fired = timer.tick(delta);
+        if fired > 0 {
+            finished.push((index, fired, timer.action));
+        }
+    })?;
+
+    let mut expired = vec![];
+    world.query().for_each::<(Lifetime,), _>(|index, (lifetime,)| {
+        if lifetime.tick(delta) {
+            expired.push(index);
+        }
+    })?;
+
+    for (index, fired, action) in finished {
+        let entity = match world.entity(index) {
+            Some(entity) => entity,
+            None => continue,
+        };
+        for _ in 0..fired {
+            world.send_event(TimerFinished { entity });
+            if let Some(action) = action {
+                action(world, entity)?;
+            }
+        }
+    }
+
+    for index in expired {
+        // An earlier timer action may already have despawned it.
+        if let Some(entity) = world.entity(index) {
+            world.send_event(LifetimeExpired { entity });
+            world.delete_entity_by_id(index)?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub struct TimerPlugin;
+
+impl Plugin for TimerPlugin {
+    fn build(self, app: &mut App) {
+        app.register_component::<Timer>()
+            .register_component::<Lifetime>()
+            .add_system(tick_timers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_shot_timer_finishes_once() {
+        let mut timer = Timer::new(Duration::from_secs(5), TimerMode::Once);
+
+        assert_eq!(timer.tick(Duration::from_secs(3)), 0);
+        assert_eq!(timer.remaining(), Duration::from_secs(2));
+        assert_eq!(timer.tick(Duration::from_secs(3)), 1);
+        assert!(timer.finished() && timer.just_finished());
+        assert_eq!(timer.tick(Duration::from_secs(3)), 0);
+        assert!(timer.finished() && !timer.just_finished());
+        assert_eq!(timer.times_finished(), 1);
+    }
+
+    #[test]
+    fn repeating_timer_carries_overshoot() {
+        let mut timer = Timer::new(Duration::from_secs(2), TimerMode::Repeating);
+
+        assert_eq!(timer.tick(Duration::from_secs(5)), 2);
+        assert_eq!(timer.elapsed(), Duration::from_secs(1));
+        assert_eq!(timer.tick(Duration::from_secs(1)), 1);
+        assert_eq!(timer.times_finished(), 3);
+    }
+
+    #[test]
+    fn repeating_timer_drops_backlog_past_the_cap() {
+        let mut timer = Timer::new(Duration::from_nanos(1), TimerMode::Repeating);
+        assert_eq!(timer.tick(Duration::from_secs(10)), DEFAULT_MAX_CATCH_UP);
+        assert_eq!(timer.elapsed(), Duration::ZERO);
+
+        let mut timer = Timer::new(Duration::from_secs(3), TimerMode::Repeating).with_max_catch_up(2);
+        assert_eq!(timer.tick(Duration::MAX), 2);
+        assert!(timer.elapsed() < timer.duration());
+        assert_eq!(timer.tick(Duration::from_secs(3)), 1);
+        assert_eq!(timer.times_finished(), 3);
+    }
+
+    #[test]
+    fn lifetime_runs_out() {
+        let mut lifetime = Lifetime::from_seconds(1.0);
+
+        assert!(!lifetime.tick(Duration::from_millis(600)));
+        assert!(lifetime.tick(Duration::from_millis(600)));
+        assert_eq!(lifetime.remaining(), Duration::ZERO);
+    }
+}
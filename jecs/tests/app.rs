@@ -0,0 +1,49 @@
+use jecs::errors::Result;
+use jecs::diagnostics::{Diagnostics, DiagnosticsPlugin};
+use jecs::{App, Plugin, World};
+
+#[test]
+fn plugins_register_components_resources_and_systems() -> Result<()> {
+    let mut app = App::new();
+    app.add_plugin(PhysicsPlugin { gravity: 2.0 })
+        .add_plugin(DiagnosticsPlugin::default());
+
+    app.world_mut()
+        .create_entity()
+        .with_component(Velocity(0.0))?;
+    app.update()?;
+    app.update()?;
+
+    let query = app.world().query().with_component::<Velocity>()?.run();
+    let borrowed_velocity = query.1[0][0].borrow();
+    let velocity = borrowed_velocity.downcast_ref::<Velocity>().unwrap();
+    assert!((velocity.0 + 4.0).abs() < f32::EPSILON);
+
+    let diagnostics = app.world().get_resource::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.tick(), 2);
+    assert_eq!(diagnostics.scope_names(), vec!["apply_gravity"]);
+    assert_eq!(diagnostics.stats("apply_gravity").unwrap().samples, 2);
+    Ok(())
+}
+
+struct PhysicsPlugin {
+    gravity: f32,
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(self, app: &mut App) {
+        app.register_component::<Velocity>()
+            .insert_resource(Gravity(self.gravity))
+            .add_system(apply_gravity);
+    }
+}
+
+fn apply_gravity(world: &mut World) -> Result<()> {
+    let gravity = world.get_resource::<Gravity>().unwrap().0;
+    world
+        .query()
+        .for_each::<(Velocity,), _>(|_, (velocity,)| velocity.0 -= gravity)
+}
+
+struct Gravity(pub f32);
+struct Velocity(pub f32);
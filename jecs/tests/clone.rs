@@ -0,0 +1,134 @@
+use jecs::errors::Result;
+use jecs::entities::map_entities::{EntityMap, MapEntities};
+use jecs::entities::name::Name;
+use jecs::entities::Entity;
+use jecs::errors::JellyEcsError;
+use jecs::World;
+
+#[test]
+fn clone_entity_copies_its_components() -> Result<()> {
+    let mut world = initialize_world();
+    world
+        .create_entity()
+        .with_component(Health(100))?
+        .with_component(Name::new("zombie"))?;
+    let zombie = world.entity(0).unwrap();
+
+    let clone = world.clone_entity(zombie)?;
+    assert_eq!(clone.id, 1);
+    assert_eq!(world.find_all_by_name("zombie"), vec![zombie, clone]);
+
+    world.query().for_each::<(Health,), _>(|index, (health,)| {
+        if index == 1 {
+            health.0 = 50;
+        }
+    })?;
+    let query = world.query().with_component::<Health>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Health>().unwrap().0, 100);
+    assert_eq!(query.1[0][1].borrow().downcast_ref::<Health>().unwrap().0, 50);
+    Ok(())
+}
+
+#[test]
+fn clone_entity_requires_cloneable_components() -> Result<()> {
+    let mut world = initialize_world();
+    world.register_component::<Opaque>();
+    world.create_entity().with_component(Health(100))?.with_component(Opaque)?;
+
+    let error = world.clone_entity(world.entity(0).unwrap()).err().unwrap();
+    match error {
+        JellyEcsError::ComponentNotCloneable { entity, component, .. } => {
+            assert_eq!(entity, world.entity(0).unwrap());
+            assert!(component.ends_with("Opaque"));
+        }
+        error => panic!("expected ComponentNotCloneable, got {}", error),
+    }
+    assert!(world.entity(1).is_none());
+    Ok(())
+}
+
+#[test]
+fn move_entities_between_worlds_remapping_references() -> Result<()> {
+    let mut staging = initialize_world();
+    staging.create_entity().with_component(Health(10))?;
+    staging.create_entity().with_component(Health(100))?;
+    staging.create_entity().with_component(Targets(Entity { id: 1, generation: 0 }))?;
+    let outsider = staging.entity(0).unwrap();
+    let human = staging.entity(1).unwrap();
+    let zombie = staging.entity(2).unwrap();
+
+    let mut live = initialize_world();
+    live.create_entity().with_component(Health(1))?;
+    let map = staging.move_entities_to(&[human, zombie], &mut live)?;
+
+    let moved_human = map.get(human).unwrap();
+    let moved_zombie = map.get(zombie).unwrap();
+    assert_eq!((moved_human.id, moved_zombie.id), (1, 2));
+    assert!(!staging.is_alive(human) && !staging.is_alive(zombie));
+    assert!(staging.is_alive(outsider));
+
+    let query = live.query().with_component::<Targets>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Targets>().unwrap().0, moved_human);
+    Ok(())
+}
+
+#[test]
+fn copying_into_a_world_missing_a_component_fails_cleanly() -> Result<()> {
+    let mut staging = initialize_world();
+    staging.create_entity().with_component(Health(10))?;
+
+    let mut live = World::new();
+    let error = staging.copy_entity_to(staging.entity(0).unwrap(), &mut live).err().unwrap();
+    assert!(matches!(error, JellyEcsError::ComponentNotRegistered { entity: None, .. }));
+    assert!(live.entity(0).is_none());
+    Ok(())
+}
+
+#[test]
+fn copies_between_worlds_reject_references_left_behind() -> Result<()> {
+    let mut staging = initialize_world();
+    staging.create_entity().with_component(Health(10))?;
+    staging.create_entity().with_component(Targets(Entity { id: 0, generation: 0 }))?;
+    let human = staging.entity(0).unwrap();
+    let zombie = staging.entity(1).unwrap();
+
+    let mut live = initialize_world();
+    live.create_entity().with_component(Health(1))?;
+    let error = staging.move_entity_to(zombie, &mut live).err().unwrap();
+    match error {
+        JellyEcsError::EntityNotCopied { entity } => assert_eq!(entity, human),
+        error => panic!("expected EntityNotCopied, got {}", error),
+    }
+    assert!(live.entity(1).is_none());
+    assert!(staging.is_alive(zombie));
+
+    // Within one world the handle still means something.
+    let clone = staging.clone_entity(zombie)?;
+    let query = staging.query().with_component::<Targets>()?.run();
+    assert_eq!(query.0, vec![zombie.id, clone.id]);
+    assert_eq!(query.1[0][1].borrow().downcast_ref::<Targets>().unwrap().0, human);
+    Ok(())
+}
+
+fn initialize_world() -> World {
+    let mut world = World::new();
+    world.register_cloneable_component::<Health>();
+    world.register_cloneable_component::<Name>();
+    world.register_cloneable_component::<Targets>();
+    world.register_map_entities::<Targets>();
+    world
+}
+
+#[derive(Clone)]
+struct Health(pub u32);
+
+#[derive(Clone)]
+struct Targets(pub Entity);
+
+impl MapEntities for Targets {
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0 = map.map(self.0);
+    }
+}
+
+struct Opaque;
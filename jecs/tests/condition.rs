@@ -0,0 +1,91 @@
+use jecs::errors::Result;
+use jecs::app::System;
+use jecs::condition::{every_n_ticks, not, on_event, resource_exists, resource_matches, Condition};
+use jecs::state::State;
+use jecs::{App, World};
+
+#[test]
+fn gate_systems_on_run_conditions() -> Result<()> {
+    let mut app = App::new();
+    app.insert_resource(ClickedLocation(None))
+        .insert_resource(Log(vec![]))
+        .add_system_run_if(
+            spawn_zombie,
+            resource_matches(|clicked: &ClickedLocation| clicked.0.is_some()),
+        )
+        .add_system_run_if(bite_humans, on_event::<Bitten>())
+        .add_boxed_system(System::new("every other tick", log("even")).run_if(every_n_ticks(2)));
+
+    app.update()?;
+    assert_eq!(logged(&app), vec!["even"]);
+
+    app.world_mut().get_resource_mut::<ClickedLocation>().unwrap().0 = Some((1.0, 1.0));
+    app.update()?;
+    assert_eq!(logged(&app), vec!["even", "spawn"]);
+
+    app.world_mut().send_event(Bitten);
+    app.update()?;
+    assert_eq!(logged(&app), vec!["even", "spawn", "bite", "even"]);
+
+    app.update()?;
+    assert_eq!(logged(&app), vec!["even", "spawn", "bite", "even"]);
+    Ok(())
+}
+
+#[test]
+fn combined_conditions_stack_with_states() -> Result<()> {
+    let mut app = App::new();
+    app.add_state(Mode::Paused)
+        .insert_resource(Log(vec![]))
+        .add_system_run_if(
+            log("paused and clicked"),
+            (|world: &World| world.get_resource::<State<Mode>>().unwrap().get() == &Mode::Paused)
+                .and(resource_matches(|clicked: &ClickedLocation| clicked.0.is_some()))
+                .and(not(resource_exists::<Frozen>())),
+        );
+
+    app.update()?;
+    assert!(logged(&app).is_empty());
+
+    app.insert_resource(ClickedLocation(Some((0.0, 0.0))));
+    app.update()?;
+    assert_eq!(logged(&app), vec!["paused and clicked"]);
+
+    app.insert_resource(Frozen);
+    app.update()?;
+    assert_eq!(logged(&app), vec!["paused and clicked"]);
+    Ok(())
+}
+
+fn spawn_zombie(world: &mut World) -> Result<()> {
+    world.get_resource_mut::<ClickedLocation>().unwrap().0 = None;
+    world.get_resource_mut::<Log>().unwrap().0.push("spawn");
+    Ok(())
+}
+
+fn bite_humans(world: &mut World) -> Result<()> {
+    world.drain_events::<Bitten>();
+    world.get_resource_mut::<Log>().unwrap().0.push("bite");
+    Ok(())
+}
+
+fn log(message: &'static str) -> impl FnMut(&mut World) -> Result<()> {
+    move |world| {
+        world.get_resource_mut::<Log>().unwrap().0.push(message);
+        Ok(())
+    }
+}
+
+fn logged(app: &App) -> Vec<&'static str> {
+    app.world().get_resource::<Log>().unwrap().0.clone()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Mode {
+    Paused,
+}
+
+struct ClickedLocation(pub Option<(f32, f32)>);
+struct Bitten;
+struct Frozen;
+struct Log(pub Vec<&'static str>);
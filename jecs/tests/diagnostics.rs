@@ -0,0 +1,48 @@
+use eyre::Result;
+use jecs::diagnostics::Diagnostics;
+use jecs::World;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn profile_systems_run_against_the_world() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Health>();
+    world.add_resource(Diagnostics::new().with_tracing());
+    world.create_entity().with_component(Health(100))?;
+
+    for _ in 0..3 {
+        world.profile("damage", |world| {
+            world.query().for_each::<(Health,), _>(|_, (health,)| health.0 -= 1)
+        })?;
+        world.profile("idle", |_| thread::sleep(Duration::from_millis(1)));
+        world.get_resource_mut::<Diagnostics>().unwrap().finish_tick();
+    }
+
+    let diagnostics = world.get_resource::<Diagnostics>().unwrap();
+    assert_eq!(diagnostics.scope_names(), vec!["damage", "idle"]);
+    let idle = diagnostics.stats("idle").unwrap();
+    assert_eq!(idle.samples, 3);
+    assert!(idle.min >= Duration::from_millis(1));
+    assert!(idle.min <= idle.avg && idle.avg <= idle.max);
+
+    let path = std::env::temp_dir().join("jecs_profile_systems_trace.json");
+    diagnostics.write_chrome_trace(&path)?;
+    let trace: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    assert_eq!(trace["traceEvents"].as_array().unwrap().len(), 6);
+    std::fs::remove_file(path)?;
+
+    let query = world.query().with_component::<Health>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Health>().unwrap().0, 97);
+    Ok(())
+}
+
+#[test]
+fn profiling_without_diagnostics_just_runs_the_scope() {
+    let mut world = World::new();
+    let answer = world.profile("answer", |_| 42);
+    assert_eq!(answer, 42);
+    assert!(world.get_resource::<Diagnostics>().is_none());
+}
+
+struct Health(pub u32);
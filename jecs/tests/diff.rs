@@ -0,0 +1,44 @@
+use eyre::Result;
+use jecs::World;
+use serde::Serialize;
+
+#[test]
+fn diffs_name_the_differing_component_types() -> Result<()> {
+    let mut original = World::new();
+    let mut replayed = World::new();
+    for world in [&mut original, &mut replayed] {
+        world.register_hashed_component::<Location>();
+        world.register_hashed_component::<Speed>();
+        world.register_component::<Untracked>();
+        world
+            .create_entity()
+            .with_component(Location(1.0, 2.0))?
+            .with_component(Speed(3.0))?
+            .with_component(Untracked(0))?;
+    }
+
+    replayed.set_component(Untracked(1), 0)?;
+    assert!(original.diff(&replayed)?.is_empty());
+    assert_eq!(original.hash()?, replayed.hash()?);
+
+    replayed.set_component(Speed(3.5), 0)?;
+    replayed.create_entity().with_component(Location(0.0, 0.0))?;
+
+    let diff = original.diff(&replayed)?;
+    assert!(diff.only_in_left.is_empty());
+    assert_eq!(diff.only_in_right, vec![replayed.entity(1).unwrap()]);
+    assert_eq!(diff.components.len(), 1);
+    assert_eq!(diff.components[0].type_name, "Speed");
+    // Without `Debug` or reflection the values can't be shown.
+    assert_eq!(diff.components[0].left.as_deref(), Some("?"));
+    assert_ne!(original.hash()?, replayed.hash()?);
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct Location(f32, f32);
+
+#[derive(Serialize)]
+struct Speed(f32);
+
+struct Untracked(#[allow(dead_code)] u32);
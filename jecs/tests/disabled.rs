@@ -0,0 +1,75 @@
+use jecs::entities::disabled::Disabled;
+use jecs::errors::{JellyEcsError, Result};
+use jecs::World;
+
+#[test]
+fn disabled_entities_leave_queries_with_their_components_intact() -> Result<()> {
+    let mut world = initialize_world()?;
+    let projectile = world.entity(1).unwrap();
+
+    world.disable(projectile)?;
+    assert!(world.is_disabled(projectile));
+    assert!(world.is_alive(projectile));
+    assert_eq!(speeds(&world)?, vec![1]);
+
+    let mut included = vec![];
+    world
+        .query()
+        .include_disabled()
+        .for_each::<(Speed,), _>(|_, (speed,)| included.push(speed.0))?;
+    assert_eq!(included, vec![1, 2]);
+
+    world.enable(projectile)?;
+    assert!(!world.is_disabled(projectile));
+    assert_eq!(speeds(&world)?, vec![1, 2]);
+    Ok(())
+}
+
+#[test]
+fn disabling_is_idempotent_and_checks_the_entity() -> Result<()> {
+    let mut world = initialize_world()?;
+    let agent = world.entity(0).unwrap();
+
+    world.enable(agent)?;
+    world.disable(agent)?;
+    world.disable(agent)?;
+    assert_eq!(world.query().with_component::<Disabled>()?.run().0, vec![0]);
+
+    world.delete_entity_by_id(0)?;
+    assert!(!world.is_disabled(agent));
+    assert!(matches!(
+        world.disable(agent),
+        Err(JellyEcsError::EntityDoesNotExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn disabling_can_be_undone() -> Result<()> {
+    let mut world = initialize_world()?;
+    let agent = world.entity(0).unwrap();
+    world.enable_journal();
+
+    world.disable(agent)?;
+    world.undo();
+    assert!(!world.is_disabled(agent));
+    world.redo();
+    assert!(world.is_disabled(agent));
+    Ok(())
+}
+
+fn speeds(world: &World) -> Result<Vec<u32>> {
+    let mut speeds = vec![];
+    world.query().for_each::<(Speed,), _>(|_, (speed,)| speeds.push(speed.0))?;
+    Ok(speeds)
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_component::<Speed>();
+    world.create_entity().with_component(Speed(1))?;
+    world.create_entity().with_component(Speed(2))?;
+    Ok(world)
+}
+
+struct Speed(u32);
@@ -0,0 +1,143 @@
+use eyre::Result;
+use jecs::entities::dynamic::{DynamicComponent, DynamicLayout, FieldType, Value};
+use jecs::World;
+
+#[test]
+fn query_dynamic_components_alongside_static_ones() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    let infected = world.register_dynamic_component(
+        DynamicLayout::new("Infected")
+            .with_field("incubation", FieldType::F32)
+            .with_field("contagious", FieldType::Bool),
+    );
+
+    let infection = world
+        .new_dynamic_component(infected)?
+        .with("incubation", Value::F32(5.0))?;
+    world
+        .create_entity()
+        .with_component(Location)?
+        .with_dynamic_component(infection)?;
+    world.create_entity().with_component(Location)?;
+
+    let query = world
+        .query()
+        .with_component::<Location>()?
+        .with_component_id(infected)?
+        .run();
+    assert_eq!(query.0, vec![0]);
+
+    {
+        let mut borrowed_infection = query.1[1][0].borrow_mut();
+        let infection = borrowed_infection.downcast_mut::<DynamicComponent>().unwrap();
+        assert_eq!(infection.get("incubation").and_then(Value::as_f32), Some(5.0));
+        infection.set("contagious", Value::Bool(true))?;
+    }
+
+    let query = world.query().with_component_id(infected)?.run();
+    let borrowed_infection = query.1[0][0].borrow();
+    let infection = borrowed_infection.downcast_ref::<DynamicComponent>().unwrap();
+    assert_eq!(infection.get("contagious"), Some(&Value::Bool(true)));
+
+    Ok(())
+}
+
+#[test]
+fn add_and_delete_dynamic_components() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    let health = world.register_dynamic_component(
+        DynamicLayout::new("Health").with_field("points", FieldType::I64),
+    );
+    world.create_entity().with_component(Location)?;
+
+    let component = world.new_dynamic_component(health)?.with("points", Value::I64(10))?;
+    world.add_dynamic_component_by_entity_id(component, 0)?;
+    assert_eq!(world.query().with_component_id(health)?.run().0, vec![0]);
+
+    world.delete_component_by_id(health, 0)?;
+    assert!(world.query().with_component_id(health)?.run().0.is_empty());
+    assert_eq!(world.query().with_component::<Location>()?.run().0, vec![0]);
+    Ok(())
+}
+
+#[test]
+fn dynamic_components_are_listed_in_the_registry() {
+    let mut world = World::new();
+    let strain = world.register_dynamic_component(
+        DynamicLayout::new("Strain").with_field("name", FieldType::String),
+    );
+
+    let info = world.registry().get_by_name("Strain").unwrap().unwrap();
+    assert_eq!(info.id(), strain);
+
+    let component = world
+        .new_dynamic_component(strain)
+        .unwrap()
+        .with("name", Value::String("t-virus".to_owned()))
+        .unwrap();
+    let fields = info.reflect(&component).unwrap();
+    assert_eq!(fields, vec![("name".to_owned(), "String(\"t-virus\")".to_owned())]);
+}
+
+#[test]
+fn dynamic_components_from_other_worlds_are_rejected() -> Result<()> {
+    let mut other = World::new();
+    let foreign = other.register_dynamic_component(DynamicLayout::new("Foreign"));
+    let component = other.new_dynamic_component(foreign)?;
+
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.create_entity().with_component(Location)?;
+    assert!(world.add_dynamic_component_by_entity_id(component, 0).is_err());
+    assert!(world.new_dynamic_component(foreign).is_err());
+    Ok(())
+}
+
+#[test]
+fn dynamic_components_with_a_colliding_index_are_rejected() -> Result<()> {
+    let mut other = World::new();
+    let foreign = other.register_dynamic_component(DynamicLayout::new("Foreign").with_field("name", FieldType::String));
+    let component = other.new_dynamic_component(foreign)?;
+
+    let mut world = World::new();
+    let infected = world.register_dynamic_component(DynamicLayout::new("Infected").with_field("incubation", FieldType::F32));
+    assert_eq!(foreign, infected);
+
+    let infection = world.new_dynamic_component(infected)?;
+    world.create_entity().with_dynamic_component(infection)?;
+    assert!(world.add_dynamic_component_by_entity_id(component.clone(), 0).is_err());
+    assert!(world.create_entity().with_dynamic_component(component).is_err());
+
+    let query = world.query().with_component_id(infected)?.run();
+    assert_eq!(query.0, vec![0]);
+    Ok(())
+}
+
+#[test]
+fn worlds_hold_more_components_than_fit_in_one_word() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    let ids: Vec<_> = (0..200)
+        .map(|index| world.register_dynamic_component(DynamicLayout::new(format!("Mod{}", index))))
+        .collect();
+    let last = *ids.last().unwrap();
+
+    let component = world.new_dynamic_component(last)?;
+    world.create_entity().with_component(Location)?;
+    world
+        .create_entity()
+        .with_component(Location)?
+        .with_dynamic_component(component)?;
+
+    assert_eq!(world.query().with_component_id(last)?.run().0, vec![1]);
+    assert_eq!(world.query().with_component::<Location>()?.run().0, vec![0, 1]);
+    assert!(world.query().with_component_id(ids[100])?.run().0.is_empty());
+
+    world.delete_component_by_id(last, 1)?;
+    assert!(world.query().with_component_id(last)?.run().0.is_empty());
+    Ok(())
+}
+
+struct Location;
@@ -147,5 +147,42 @@ fn delete_entity() -> Result<()> {
     Ok(())
 }
 
+#[test]
+#[allow(clippy::float_cmp)]
+fn iterate_over_pairs_of_entities_mutably() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_component::<Size>();
+
+    world.create_entity().with_component(Location(0.0, 0.0))?;
+    world.create_entity().with_component(Size(10.0))?;
+    world.create_entity().with_component(Location(1.0, 0.0))?;
+    world.create_entity().with_component(Location(2.0, 0.0))?;
+
+    let mut query = world.query();
+    query.with_component::<Location>()?;
+
+    let mut pairs = 0;
+    for [(_, first), (_, second)] in query.iter_combinations::<2>() {
+        let mut borrowed_first = first[0].borrow_mut();
+        let mut borrowed_second = second[0].borrow_mut();
+        let first_location = borrowed_first.downcast_mut::<Location>().unwrap();
+        let second_location = borrowed_second.downcast_mut::<Location>().unwrap();
+        first_location.1 += 1.0;
+        second_location.1 += 1.0;
+        pairs += 1;
+    }
+    assert_eq!(pairs, 3);
+
+    let query = world.query().with_component::<Location>()?.run();
+    for location in &query.1[0] {
+        let borrowed_location = location.borrow();
+        let location = borrowed_location.downcast_ref::<Location>().unwrap();
+        assert_eq!(location.1, 2.0);
+    }
+
+    Ok(())
+}
+
 struct Location(pub f32, pub f32);
 struct Size(pub f32);
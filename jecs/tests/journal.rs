@@ -0,0 +1,129 @@
+use eyre::Result;
+use jecs::entities::name::Name;
+use jecs::World;
+
+#[test]
+fn undo_and_redo_structural_changes() -> Result<()> {
+    let mut world = initialize_world();
+    world.create_entity().with_component(Health(100))?;
+    let zombie = world.entity(0).unwrap();
+    world.add_component_by_entity_id(Name::new("zombie"), 0)?;
+    world.delete_component_by_entity_id::<Health>(0)?;
+    world.delete_entity_by_id(0)?;
+    assert_eq!(world.journal().unwrap().undo_len(), 4);
+
+    assert!(world.undo());
+    assert!(world.is_alive(zombie));
+    assert_eq!(world.find_by_name("zombie"), Some(zombie));
+
+    assert!(world.undo());
+    assert_eq!(health(&world, 0), Some(100));
+
+    assert!(world.undo());
+    assert!(world.find_by_name("zombie").is_none());
+
+    assert!(world.undo());
+    assert!(!world.is_alive(zombie));
+    assert!(!world.undo());
+
+    assert!(world.redo());
+    assert!(world.is_alive(zombie));
+    assert_eq!(health(&world, 0), Some(100));
+    assert!(world.redo() && world.redo() && world.redo());
+    assert!(!world.is_alive(zombie));
+    assert!(!world.redo());
+    Ok(())
+}
+
+#[test]
+fn undo_component_writes() -> Result<()> {
+    let mut world = initialize_world();
+    world.create_entity().with_component(Health(100))?;
+    world.set_component(Health(40), 0)?;
+    world.set_component(Health(10), 0)?;
+
+    world.undo();
+    assert_eq!(health(&world, 0), Some(40));
+    world.undo();
+    assert_eq!(health(&world, 0), Some(100));
+    world.redo();
+    assert_eq!(health(&world, 0), Some(40));
+
+    world.register_component::<Speed>();
+    assert!(world.set_component(Speed, 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn transactions_are_undone_as_one_step() -> Result<()> {
+    let mut world = initialize_world();
+    world.create_entity().with_component(Health(100))?;
+
+    world.begin_transaction();
+    world.create_entity().with_component(Health(50))?;
+    world.set_component(Health(90), 0)?;
+    world.delete_entity_by_id(1)?;
+    world.commit_transaction();
+    assert_eq!(world.journal().unwrap().undo_len(), 2);
+
+    world.undo();
+    assert_eq!(health(&world, 0), Some(100));
+    assert!(world.entity(1).is_none());
+
+    world.redo();
+    assert_eq!(health(&world, 0), Some(90));
+    assert!(world.entity(1).is_none());
+    Ok(())
+}
+
+#[test]
+fn new_changes_clear_the_redo_history() -> Result<()> {
+    let mut world = initialize_world();
+    world.create_entity().with_component(Health(100))?;
+    world.undo();
+    assert!(world.journal().unwrap().can_redo());
+
+    world.create_entity().with_component(Health(50))?;
+    assert!(!world.journal().unwrap().can_redo());
+    assert_eq!(health(&world, 0), Some(50));
+    assert_eq!(world.entity(0).unwrap().generation, 1);
+    Ok(())
+}
+
+#[test]
+fn spawns_are_undone_together_with_their_components() -> Result<()> {
+    let mut world = initialize_world();
+    let zombie = world
+        .create_entity()
+        .with_component(Health(100))?
+        .with_component(Name::new("zombie"))?
+        .entity();
+    assert_eq!(world.journal().unwrap().undo_len(), 1);
+
+    assert!(world.undo());
+    assert!(!world.is_alive(zombie));
+    assert!(world.find_by_name("zombie").is_none());
+
+    assert!(world.redo());
+    assert_eq!(world.find_by_name("zombie"), Some(zombie));
+    assert_eq!(health(&world, zombie.id), Some(100));
+    Ok(())
+}
+
+fn initialize_world() -> World {
+    let mut world = World::new();
+    world.register_component::<Health>();
+    world.register_component::<Name>();
+    world.enable_journal();
+    world
+}
+
+fn health(world: &World, index: usize) -> Option<u32> {
+    let query = world.query().with_component::<Health>().ok()?.run();
+    let position = query.0.iter().position(|matched| *matched == index)?;
+    let health = query.1[0][position].borrow().downcast_ref::<Health>().map(|health| health.0);
+    health
+}
+
+struct Health(pub u32);
+struct Speed;
@@ -0,0 +1,92 @@
+use jecs::errors::Result;
+use jecs::entities::name::Name;
+use jecs::errors::JellyEcsError;
+use jecs::World;
+
+#[test]
+fn find_entities_by_name() -> Result<()> {
+    let mut world = initialize_world()?;
+
+    let player = world.find_by_name("player").unwrap();
+    assert_eq!(player.id, 0);
+    assert_eq!(world.find_all_by_name("zombie").len(), 2);
+    assert!(world.find_by_name("nobody").is_none());
+
+    world.delete_entity_by_id(1)?;
+    let zombies: Vec<usize> = world.find_all_by_name("zombie").iter().map(|entity| entity.id).collect();
+    assert_eq!(zombies, vec![2]);
+
+    world.create_entity().with_component(Name::new("player"))?;
+    let reused = world.entity(1).unwrap();
+    assert_eq!(world.find_all_by_name("player"), vec![player, reused]);
+    Ok(())
+}
+
+#[test]
+fn renaming_with_set_component_updates_lookups() -> Result<()> {
+    let mut world = initialize_world()?;
+    let zombie = world.entity(1).unwrap();
+
+    world.set_component(Name::new("survivor"), zombie.id)?;
+    assert_eq!(world.find_by_name("survivor"), Some(zombie));
+    let zombies: Vec<usize> = world.find_all_by_name("zombie").iter().map(|entity| entity.id).collect();
+    assert_eq!(zombies, vec![2]);
+
+    world.query().for_each::<(Name,), _>(|index, (name,)| {
+        if index == zombie.id {
+            *name = Name::new("ghost");
+        }
+    })?;
+    assert!(world.find_by_name("survivor").is_none());
+    assert!(world.find_by_name("ghost").is_none());
+    Ok(())
+}
+
+#[test]
+fn names_survive_snapshots() -> Result<()> {
+    let mut world = initialize_world()?;
+    let snapshot = world.snapshot()?;
+
+    world.add_component_by_entity_id(Name::new("ghost"), 0)?;
+    assert!(world.find_by_name("player").is_none());
+
+    world.restore(&snapshot)?;
+    assert_eq!(world.find_by_name("player").map(|entity| entity.id), Some(0));
+    assert!(world.find_by_name("ghost").is_none());
+    Ok(())
+}
+
+#[test]
+fn names_appear_in_dumps_and_errors() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_component::<Health>();
+
+    assert!(world.dump().to_string().contains("Entity 0 \"player\" (generation 0)"));
+
+    let error = world.delete_component_by_entity_id::<Health>(0).err().unwrap();
+    assert!(error.to_string().contains("entity 0 \"player\""));
+    match error {
+        JellyEcsError::ComponentNotFound { entity, name, component } => {
+            assert_eq!(entity, world.entity(0).unwrap());
+            assert_eq!(name.as_deref(), Some("player"));
+            assert!(component.ends_with("Health"));
+        }
+        error => panic!("expected ComponentNotFound, got {}", error),
+    }
+
+    let error = world.add_component_by_entity_id(Armor, 0).err().unwrap();
+    assert!(error.to_string().ends_with("(on entity 0 \"player\")"));
+    Ok(())
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_cloneable_component::<Name>();
+    world.create_entity().with_component(Name::new("player"))?;
+    world.create_entity().with_component(Name::new("zombie"))?;
+    world.create_entity().with_component(Name::from("zombie"))?;
+    Ok(world)
+}
+
+struct Health;
+struct Armor;
@@ -0,0 +1,100 @@
+#![cfg(feature = "parallel")]
+
+use jecs::errors::Result;
+use jecs::World;
+
+#[test]
+fn par_for_each_matches_sequential_iteration() -> Result<()> {
+    let sequential = steer(|world| {
+        world
+            .query()
+            .for_each::<(Location, Velocity), _>(|_, (location, velocity)| {
+                apply_steering(location, velocity)
+            })
+    })?;
+    let parallel = steer(|world| {
+        world
+            .query()
+            .with_batch_size(7)
+            .par_for_each::<(Location, Velocity), _>(|_, (location, velocity)| {
+                apply_steering(location, velocity)
+            })
+    })?;
+
+    assert_eq!(sequential, parallel);
+    Ok(())
+}
+
+#[test]
+fn par_for_each_visits_every_matching_entity_once() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_component::<Velocity>();
+    for index in 0..100 {
+        world.create_entity().with_component(Location(index as f32, 0.0))?;
+    }
+    world.create_entity().with_component(Velocity(1.0, 1.0))?;
+
+    world
+        .query()
+        .with_batch_size(0)
+        .par_for_each::<(Location,), _>(|index, (location,)| {
+            location.1 += index as f32;
+        })?;
+
+    let query = world.query().with_component::<Location>()?.run();
+    assert_eq!(query.0.len(), 100);
+    for location in &query.1[0] {
+        let borrowed_location = location.borrow();
+        let location = borrowed_location.downcast_ref::<Location>().unwrap();
+        assert!((location.0 - location.1).abs() < f32::EPSILON);
+    }
+
+    Ok(())
+}
+
+fn steer(run: impl Fn(&World) -> Result<()>) -> Result<Vec<(f32, f32, f32, f32)>> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_component::<Velocity>();
+    for index in 0..1000 {
+        let offset = index as f32;
+        world
+            .create_entity()
+            .with_component(Location(offset, offset * 0.5))?
+            .with_component(Velocity(1.0, -1.0))?;
+    }
+
+    for _ in 0..10 {
+        run(&world)?;
+    }
+
+    let query = world
+        .query()
+        .with_component::<Location>()?
+        .with_component::<Velocity>()?
+        .run();
+    let states = query.1[0]
+        .iter()
+        .zip(&query.1[1])
+        .map(|(location, velocity)| {
+            let borrowed_location = location.borrow();
+            let borrowed_velocity = velocity.borrow();
+            let location = borrowed_location.downcast_ref::<Location>().unwrap();
+            let velocity = borrowed_velocity.downcast_ref::<Velocity>().unwrap();
+            (location.0, location.1, velocity.0, velocity.1)
+        })
+        .collect();
+
+    Ok(states)
+}
+
+fn apply_steering(location: &mut Location, velocity: &mut Velocity) {
+    velocity.0 += (512.0 - location.0) * 0.01;
+    velocity.1 += (512.0 - location.1) * 0.01;
+    location.0 += velocity.0;
+    location.1 += velocity.1;
+}
+
+struct Location(pub f32, pub f32);
+struct Velocity(pub f32, pub f32);
@@ -0,0 +1,170 @@
+use jecs::errors::Result;
+use jecs::errors::JellyEcsError;
+use jecs::prefab::Prefabs;
+use jecs::World;
+use serde::Deserialize;
+use serde_json::json;
+
+const PREFABS: &str = r#"{
+    "zombie": {
+        "Position": (x: 0.0, y: 0.0),
+        "Velocity": (x: 1.0, y: -1.0),
+        "Health": 100,
+    },
+    "human": {
+        "Position": (x: 10.0, y: 10.0),
+        "Health": 50,
+    },
+}"#;
+
+#[test]
+fn spawn_prefabs_loaded_from_a_file() -> Result<()> {
+    let path = std::env::temp_dir().join("jecs_spawn_prefabs.ron");
+    std::fs::write(&path, PREFABS)?;
+    let mut world = initialize_world(Prefabs::load(&path)?);
+    std::fs::remove_file(path)?;
+
+    let zombie = world.spawn_prefab("zombie")?;
+    let human = world.spawn_prefab("human")?;
+    assert_eq!((zombie.id, human.id), (0, 1));
+
+    let query = world
+        .query()
+        .with_component::<Position>()?
+        .with_component::<Velocity>()?
+        .with_component::<Health>()?
+        .run();
+    assert_eq!(query.0, vec![0]);
+    assert_eq!(query.1[1][0].borrow().downcast_ref::<Velocity>().unwrap(), &Velocity { x: 1.0, y: -1.0 });
+    assert_eq!(query.1[2][0].borrow().downcast_ref::<Health>().unwrap(), &Health(100));
+    Ok(())
+}
+
+#[test]
+fn spawn_prefab_with_field_overrides() -> Result<()> {
+    let mut world = initialize_world(Prefabs::from_ron(PREFABS)?);
+    world.spawn_prefab_with("zombie", &json!({"Position": {"y": 4.0}, "Health": 20}))?;
+
+    let query = world
+        .query()
+        .with_component::<Position>()?
+        .with_component::<Health>()?
+        .run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Position>().unwrap(), &Position { x: 0.0, y: 4.0 });
+    assert_eq!(query.1[1][0].borrow().downcast_ref::<Health>().unwrap(), &Health(20));
+    Ok(())
+}
+
+#[test]
+fn misspelled_names_suggest_the_closest_match() -> Result<()> {
+    let mut world = initialize_world(Prefabs::from_json(
+        r#"{"zombie": {"Positon": {"x": 0.0, "y": 0.0}, "Health": 100}}"#,
+    )?);
+
+    let error = world.spawn_prefab("zombi").err().unwrap();
+    assert_eq!(error.to_string(), "There is no prefab named zombi (did you mean zombie?)");
+
+    let error = world.spawn_prefab("zombie").err().unwrap();
+    assert!(matches!(error, JellyEcsError::UnknownPrefabComponent { .. }));
+    assert_eq!(
+        error.to_string(),
+        "Prefab zombie references a component that isn't registered: Positon (did you mean Position?)"
+    );
+    assert!(world.entity(0).is_none());
+    Ok(())
+}
+
+#[test]
+fn invalid_component_values_are_reported() -> Result<()> {
+    let mut world = initialize_world(Prefabs::from_json(r#"{"zombie": {"Health": "lots"}}"#)?);
+    world.register_component::<Opaque>();
+
+    let error = world.spawn_prefab("zombie").err().unwrap();
+    assert!(error.to_string().starts_with("Prefab zombie has an invalid Health component: invalid type"));
+
+    let error = world
+        .spawn_prefab_with("zombie", &json!({"Health": 1, "Opaque": null}))
+        .err()
+        .unwrap();
+    assert!(matches!(error, JellyEcsError::ComponentNotDeserializable { .. }));
+    Ok(())
+}
+
+#[test]
+fn empty_prefabs_are_rejected() -> Result<()> {
+    let mut world = initialize_world(Prefabs::from_json(r#"{"marker": {}}"#)?);
+    world.enable_journal();
+
+    let error = world.spawn_prefab("marker").err().unwrap();
+    assert!(matches!(error, JellyEcsError::EmptyPrefab { .. }));
+    assert_eq!(world.journal().unwrap().undo_len(), 0);
+    Ok(())
+}
+
+#[test]
+fn ron_prefabs_spawn_enums_and_newtypes() -> Result<()> {
+    let mut world = initialize_world(Prefabs::from_ron(
+        r#"{
+            "zombie": {
+                "Health": Health(80),
+                "State": Chasing(target: 3),
+                "Loot": Some(5),
+            },
+            "corpse": {"State": Idle, "Loot": None},
+        }"#,
+    )?);
+    world.register_deserializable_component::<State>();
+    world.register_deserializable_component::<Loot>();
+
+    world.spawn_prefab("zombie")?;
+    world.spawn_prefab("corpse")?;
+    world.spawn_prefab_with("zombie", &json!({"State": "Idle"}))?;
+
+    let query = world.query().with_component::<State>()?.with_component::<Loot>()?.run();
+    let states: Vec<State> = query.1[0]
+        .iter()
+        .map(|state| state.borrow().downcast_ref::<State>().unwrap().clone())
+        .collect();
+    assert_eq!(states, vec![State::Chasing { target: 3 }, State::Idle, State::Idle]);
+    assert_eq!(query.1[1][1].borrow().downcast_ref::<Loot>().unwrap(), &Loot(None));
+    assert_eq!(query.1[1][2].borrow().downcast_ref::<Loot>().unwrap(), &Loot(Some(5)));
+
+    let health = world.query().with_component::<Health>()?.run();
+    assert_eq!(health.1[0][0].borrow().downcast_ref::<Health>().unwrap(), &Health(80));
+    Ok(())
+}
+
+fn initialize_world(prefabs: Prefabs) -> World {
+    let mut world = World::new();
+    world.register_deserializable_component::<Position>();
+    world.register_deserializable_component::<Velocity>();
+    world.register_deserializable_component::<Health>();
+    world.add_resource(prefabs);
+    world
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Velocity {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Health(u32);
+
+struct Opaque;
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+enum State {
+    Idle,
+    Chasing { target: u32 },
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+struct Loot(Option<u32>);
@@ -0,0 +1,65 @@
+use jecs::errors::Result;
+use jecs::entities::ComponentId;
+use jecs::errors::JellyEcsError;
+use jecs::{impl_reflect, World};
+
+#[test]
+fn registered_components_are_listed_by_name() {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.register_reflected_component::<Size>();
+
+    let names: Vec<&str> = world.registry().iter().map(|info| info.short_name()).collect();
+    assert_eq!(names, vec!["Location", "Size"]);
+
+    let size = world.registry().get_by_name("Size").unwrap().unwrap();
+    assert_eq!(size.id(), ComponentId::of::<Size>());
+    assert_eq!(size.size(), std::mem::size_of::<Size>());
+}
+
+#[test]
+fn reflect_queried_component() -> Result<()> {
+    let mut world = World::new();
+    world.register_reflected_component::<Size>();
+    world.create_entity().with_component(Size { width: 2, height: 3 })?;
+
+    let query = world.query().with_component::<Size>()?.run();
+    let info = world.registry().get(&ComponentId::of::<Size>()).unwrap();
+    let borrowed_size = query.1[0][0].borrow();
+    let fields = info.reflect(&*borrowed_size).unwrap();
+
+    assert_eq!(
+        fields,
+        vec![
+            ("width".to_owned(), "2".to_owned()),
+            ("height".to_owned(), "3".to_owned())
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn unregistered_component_errors_name_the_type() {
+    let mut world = World::new();
+    world.register_component::<Location>();
+
+    let error = world
+        .create_entity()
+        .with_component(Size { width: 1, height: 1 })
+        .err()
+        .unwrap();
+    assert!(matches!(error, JellyEcsError::ComponentNotRegistered { entity: None, .. }));
+    assert!(error.to_string().ends_with("registry::Size"));
+
+    let error = world.query().with_component::<Size>().err().unwrap();
+    assert!(error.to_string().contains("Size"));
+}
+
+struct Location;
+
+struct Size {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl_reflect!(Size { width, height });
@@ -0,0 +1,176 @@
+use jecs::entities::map_entities::EntityMap;
+use jecs::entities::Entity;
+use jecs::errors::Result;
+use jecs::events::Events;
+use jecs::relationship::{Relationship, RelationshipRemoved, Targets};
+use jecs::World;
+
+#[test]
+fn despawning_a_target_removes_relationships_to_it() -> Result<()> {
+    let mut world = initialize_world()?;
+    let human = world.entity(0).unwrap();
+    let zombie = world.entity(1).unwrap();
+    let other_zombie = world.entity(2).unwrap();
+
+    assert_eq!(world.sources::<Targets>(human), vec![zombie, other_zombie]);
+
+    world.delete_entity_by_id(human.id)?;
+    assert!(world.sources::<Targets>(human).is_empty());
+    assert!(world.is_alive(zombie));
+    assert!(world.query().with_component::<Targets>()?.run().0.is_empty());
+
+    let events = world.get_resource::<Events<RelationshipRemoved>>().unwrap();
+    let removed: Vec<(Entity, Entity, &str)> = events
+        .iter()
+        .map(|event| (event.source, event.target, event.relationship.as_str()))
+        .collect();
+    assert_eq!(removed, vec![(zombie, human, "Targets"), (other_zombie, human, "Targets")]);
+    Ok(())
+}
+
+#[test]
+fn custom_relationships_are_tracked_too() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_relationship::<Follows>();
+    let leader = world.entity(0).unwrap();
+    world
+        .create_entity()
+        .with_component(Health(5))?
+        .with_component(Follows(leader))?;
+    let follower = world.entity(3).unwrap();
+
+    assert_eq!(world.sources::<Follows>(leader), vec![follower]);
+    assert!(world.sources::<Follows>(follower).is_empty());
+
+    world.delete_entity_by_id(leader.id)?;
+    assert!(world.sources::<Follows>(leader).is_empty());
+    assert!(world.is_alive(follower));
+    Ok(())
+}
+
+#[test]
+fn undoing_a_despawn_restores_its_relationships() -> Result<()> {
+    let mut world = initialize_world()?;
+    let human = world.entity(0).unwrap();
+    world.enable_journal();
+
+    world.delete_entity_by_id(human.id)?;
+    assert_eq!(world.journal().unwrap().undo_len(), 1);
+
+    world.undo();
+    assert!(world.is_alive(human));
+    assert_eq!(world.sources::<Targets>(human).len(), 2);
+
+    // Undo is a change of its own; it doesn't take back events already sent.
+    assert_eq!(world.drain_events::<RelationshipRemoved>().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn rolled_back_despawns_send_no_events() -> Result<()> {
+    let mut world = initialize_world()?;
+    let human = world.entity(0).unwrap();
+
+    let result: Result<()> = world.transaction(|world| {
+        world.delete_entity_by_id(human.id)?;
+        world.delete_entity_by_id(human.id + 10)
+    });
+    assert!(result.is_err());
+    assert!(world.is_alive(human));
+    assert_eq!(world.sources::<Targets>(human).len(), 2);
+    assert!(world.drain_events::<RelationshipRemoved>().is_empty());
+    Ok(())
+}
+
+#[test]
+fn cloned_relationships_follow_the_entity_map() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_cloneable_component::<Health>();
+    world.register_cloneable_component::<Targets>();
+    let human = world.entity(0).unwrap();
+    let zombie = world.entity(1).unwrap();
+
+    let map: EntityMap = world.clone_entities(&[human, zombie])?;
+    let new_human = map.map(human);
+    let new_zombie = map.map(zombie);
+
+    assert_eq!(world.sources::<Targets>(new_human), vec![new_zombie]);
+    Ok(())
+}
+
+#[test]
+fn sources_follow_retargeting_and_restores() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_cloneable_component::<Health>();
+    world.register_cloneable_component::<Targets>();
+    let human = world.entity(0).unwrap();
+    let zombie = world.entity(1).unwrap();
+    let other_zombie = world.entity(2).unwrap();
+    let snapshot = world.snapshot()?;
+
+    world.set_component(Targets(other_zombie), zombie.id)?;
+    assert_eq!(world.sources::<Targets>(human), vec![other_zombie]);
+    assert_eq!(world.sources::<Targets>(other_zombie), vec![zombie]);
+
+    world.delete_component_by_entity_id::<Targets>(other_zombie.id)?;
+    assert!(world.sources::<Targets>(human).is_empty());
+
+    world.restore(&snapshot)?;
+    assert_eq!(world.sources::<Targets>(human), vec![zombie, other_zombie]);
+    assert!(world.sources::<Targets>(other_zombie).is_empty());
+    Ok(())
+}
+
+#[test]
+fn retargeting_through_a_query_is_tracked() -> Result<()> {
+    let mut world = initialize_world()?;
+    let human = world.entity(0).unwrap();
+    let zombie = world.entity(1).unwrap();
+    let other_zombie = world.entity(2).unwrap();
+
+    world.query().for_each::<(Targets,), _>(|index, (targets,)| {
+        if index == zombie.id {
+            targets.set_target(other_zombie);
+        }
+    })?;
+    assert_eq!(world.sources::<Targets>(human), vec![other_zombie]);
+    assert_eq!(world.sources::<Targets>(other_zombie), vec![zombie]);
+
+    world.delete_entity_by_id(other_zombie.id)?;
+    assert!(world.sources::<Targets>(other_zombie).is_empty());
+    let (remaining, _) = world.query().with_component::<Targets>()?.run();
+    assert!(remaining.is_empty());
+    Ok(())
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_component::<Health>();
+    world.register_relationship::<Targets>();
+
+    world.create_entity().with_component(Health(100))?;
+    let human = world.entity(0).unwrap();
+    for _ in 0..2 {
+        world
+            .create_entity()
+            .with_component(Health(50))?
+            .with_component(Targets(human))?;
+    }
+    Ok(world)
+}
+
+#[derive(Clone)]
+struct Health(#[allow(dead_code)] u32);
+
+#[derive(Clone, Copy)]
+struct Follows(Entity);
+
+impl Relationship for Follows {
+    fn target(&self) -> Entity {
+        self.0
+    }
+
+    fn set_target(&mut self, target: Entity) {
+        self.0 = target;
+    }
+}
@@ -0,0 +1,117 @@
+use jecs::entities::removed::RemovedComponents;
+use jecs::errors::Result;
+use jecs::{App, World};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn readers_keep_their_own_cursor() -> Result<()> {
+    let mut world = initialize_world()?;
+    let first = world.entity(0).unwrap();
+    let second = world.entity(1).unwrap();
+    let mut early = RemovedComponents::<Health>::new();
+    let mut late = RemovedComponents::<Health>::default();
+
+    world.delete_component_by_entity_id::<Health>(0)?;
+    assert_eq!(early.read(&world), vec![first]);
+    assert!(early.read(&world).is_empty());
+
+    world.delete_entity_by_id(1)?;
+    assert_eq!(early.read(&world), vec![second]);
+    assert_eq!(late.read(&world), vec![first, second]);
+    assert!(RemovedComponents::<Speed>::new().read(&world).is_empty());
+    Ok(())
+}
+
+#[test]
+fn despawns_remove_every_component() -> Result<()> {
+    let mut world = initialize_world()?;
+    let entity = world.entity(1).unwrap();
+
+    world.delete_entity_by_id(1)?;
+    assert_eq!(RemovedComponents::<Health>::new().read(&world), vec![entity]);
+    assert_eq!(RemovedComponents::<Armor>::new().read(&world), vec![entity]);
+    Ok(())
+}
+
+#[test]
+fn removals_last_until_the_end_of_the_next_update() -> Result<()> {
+    let seen = Rc::new(RefCell::new(vec![]));
+    let mut app = App::new();
+    *app.world_mut() = initialize_world()?;
+    let mut removed = RemovedComponents::<Health>::new();
+    let reported = seen.clone();
+    app.add_system_with_name("report", move |world| {
+        reported.borrow_mut().push(removed.read(world).len());
+        Ok(())
+    });
+
+    app.world_mut().delete_component_by_entity_id::<Health>(0)?;
+    app.update()?;
+    app.update()?;
+    assert_eq!(*seen.borrow(), vec![1, 0]);
+
+    let entity = app.world().entity(1).unwrap();
+    app.world_mut().delete_entity_by_id(1)?;
+    app.update()?;
+    // A reader that shows up late still sees removals from the last update.
+    assert_eq!(RemovedComponents::<Health>::new().read(app.world()), vec![entity]);
+    app.update()?;
+    assert!(RemovedComponents::<Health>::new().read(app.world()).is_empty());
+    Ok(())
+}
+
+#[test]
+fn rolled_back_removals_are_not_reported() -> Result<()> {
+    let mut world = initialize_world()?;
+    let mut reader = RemovedComponents::<Health>::new();
+    world.delete_component_by_entity_id::<Health>(0)?;
+
+    let result: Result<()> = world.transaction(|world| {
+        world.delete_entity_by_id(1)?;
+        world.create_entity().with_component(Health)?;
+        world.delete_component_by_entity_id::<Armor>(5)
+    });
+    assert!(result.is_err());
+    assert_eq!(reader.read(&world), vec![world.entity(0).unwrap()]);
+    assert!(RemovedComponents::<Armor>::new().read(&world).is_empty());
+    Ok(())
+}
+
+#[test]
+fn restoring_a_snapshot_reports_what_it_drops() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_cloneable_component::<Health>();
+    world.register_cloneable_component::<Armor>();
+    let snapshot = world.snapshot()?;
+    let first = world.entity(0).unwrap();
+
+    world.create_entity().with_component(Health)?;
+    let spawned = world.entity(2).unwrap();
+    world.add_component_by_entity_id(Speed, 0)?;
+    let mut health = RemovedComponents::<Health>::new();
+    let mut speed = RemovedComponents::<Speed>::new();
+
+    world.restore(&snapshot)?;
+    assert_eq!(health.read(&world), vec![spawned]);
+    assert_eq!(speed.read(&world), vec![first]);
+    Ok(())
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_component::<Health>();
+    world.register_component::<Armor>();
+    world.register_component::<Speed>();
+    world.create_entity().with_component(Health)?.with_component(Armor)?;
+    world.create_entity().with_component(Health)?.with_component(Armor)?;
+    Ok(world)
+}
+
+#[derive(Clone)]
+struct Health;
+
+#[derive(Clone)]
+struct Armor;
+
+struct Speed;
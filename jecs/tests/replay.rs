@@ -0,0 +1,216 @@
+use jecs::errors::Result;
+use jecs::errors::JellyEcsError;
+use jecs::replay::{Command, Replay};
+use jecs::rng::Rng;
+use jecs::time::{ManualClock, Time, TimePlugin};
+use jecs::{App, World};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[test]
+fn replays_reproduce_the_recorded_world() -> Result<()> {
+    let clock = ManualClock::new();
+    let mut app = simulation(clock.clone(), 1.0);
+    app.start_recording(7);
+
+    for (tick, millis) in [16, 17, 33, 15, 16].iter().enumerate() {
+        clock.advance(Duration::from_millis(*millis));
+        if tick % 2 == 0 {
+            app.send_command(SpawnAt { x: tick as f32, y: 0.0 })?;
+        }
+        app.update()?;
+    }
+    app.send_command(Freeze)?;
+    app.update()?;
+
+    let replay = Replay::from_bytes(&app.stop_recording().unwrap().to_bytes()?)?;
+    assert_eq!(replay.len(), 6);
+    assert_eq!(replay.seed(), 7);
+    assert_eq!(replay.ticks()[2].commands(), vec!["SpawnAt"]);
+    assert_eq!(replay.ticks()[0].delta(), Duration::ZERO);
+    assert_eq!(replay.ticks()[1].delta(), Duration::from_millis(17));
+
+    // The fresh app's clock never moves; the replay supplies the frame times.
+    let mut replayed = simulation(ManualClock::new(), 1.0);
+    replayed.play(replay.clone());
+    for _ in 0..replay.len() {
+        replayed.update()?;
+    }
+
+    assert!(!replayed.is_replaying());
+    assert_eq!(replayed.world().hash()?, app.world().hash()?);
+    assert_eq!(replayed.world().hash()?, replay.ticks()[5].hash());
+    assert_eq!(positions(&replayed)?, positions(&app)?);
+    Ok(())
+}
+
+#[test]
+fn diverging_replays_report_the_tick() -> Result<()> {
+    let clock = ManualClock::new();
+    let mut app = simulation(clock.clone(), 1.0);
+    app.start_recording(3);
+    app.send_command(SpawnAt { x: 0.0, y: 0.0 })?;
+    for _ in 0..3 {
+        clock.advance(Duration::from_millis(16));
+        app.update()?;
+    }
+    let replay = app.stop_recording().unwrap();
+
+    let mut replayed = simulation(ManualClock::new(), 2.0);
+    replayed.play(replay);
+    replayed.update()?;
+
+    let error = replayed.update().unwrap_err();
+    match error {
+        JellyEcsError::ReplayDiverged(tick, _, _) => assert_eq!(tick, 1),
+        error => panic!("expected a divergence, got {}", error),
+    }
+    assert!(!replayed.is_replaying());
+    Ok(())
+}
+
+#[test]
+fn replays_notice_extra_rng_draws() -> Result<()> {
+    let mut app = simulation(ManualClock::new(), 1.0);
+    app.start_recording(5);
+    app.update()?;
+    app.update()?;
+    let replay = app.stop_recording().unwrap();
+
+    // Nothing is spawned, so only the generator's state can tell them apart.
+    let mut replayed = simulation(ManualClock::new(), 1.0);
+    replayed.add_system_with_name("draw", |world| {
+        world.get_resource_mut::<Rng>().unwrap().next_u64();
+        Ok(())
+    });
+    replayed.play(replay);
+    assert!(matches!(replayed.update(), Err(JellyEcsError::ReplayDiverged(0, _, _))));
+    Ok(())
+}
+
+#[test]
+fn live_commands_are_ignored_during_playback() -> Result<()> {
+    let mut app = simulation(ManualClock::new(), 1.0);
+    app.start_recording(1);
+    app.update()?;
+    app.update()?;
+    let replay = app.stop_recording().unwrap();
+
+    let mut replayed = simulation(ManualClock::new(), 1.0);
+    replayed.play(replay);
+    replayed.send_command(SpawnAt { x: 1.0, y: 1.0 })?;
+    replayed.update()?;
+    replayed.update()?;
+
+    assert!(positions(&replayed)?.is_empty());
+    assert!(replayed.send_command(Unregistered).is_err());
+    Ok(())
+}
+
+#[test]
+fn hashed_resources_are_part_of_the_world_hash() -> Result<()> {
+    let mut world = World::new();
+    world.add_resource(Score(1));
+    let before = world.hash()?;
+
+    world.get_resource_mut::<Score>().unwrap().0 = 2;
+    assert_eq!(world.hash()?, before);
+
+    world.register_hashed_resource::<Score>();
+    let hashed = world.hash()?;
+    world.get_resource_mut::<Score>().unwrap().0 = 3;
+    assert_ne!(world.hash()?, hashed);
+    Ok(())
+}
+
+fn simulation(clock: ManualClock, speed: f32) -> App {
+    let mut app = App::new();
+    app.add_plugin(TimePlugin::default())
+        .insert_resource(Time::with_clock(clock))
+        .insert_resource(Frozen(false))
+        .register_command::<SpawnAt>()
+        .register_command::<Freeze>()
+        .add_system_with_name("wander", move |world| wander(world, speed));
+    app.world_mut().register_hashed_component::<Position>();
+    app.world_mut().register_hashed_component::<Heading>();
+    app
+}
+
+// Headings are drawn from the seeded `Rng`, so they only match between runs
+// when the replay's seed is used.
+fn wander(world: &mut World, speed: f32) -> Result<()> {
+    if world.get_resource::<Frozen>().unwrap().0 {
+        return Ok(());
+    }
+
+    let delta = world.get_resource::<Time>().unwrap().delta_seconds();
+    let mut rng = world.get_resource::<Rng>().unwrap().clone();
+    world
+        .query()
+        .for_each::<(Position, Heading), _>(|_, (position, heading)| {
+            heading.0 += rng.range(-0.5, 0.5);
+            position.x += heading.0.cos() * speed * delta;
+            position.y += heading.0.sin() * speed * delta;
+        })?;
+    world.add_resource(rng);
+
+    Ok(())
+}
+
+fn positions(app: &App) -> Result<Vec<(f32, f32)>> {
+    let mut positions = vec![];
+    app.world()
+        .query()
+        .for_each::<(Position,), _>(|_, (position,)| positions.push((position.x, position.y)))?;
+    Ok(positions)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Position {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Heading(f32);
+
+struct Frozen(bool);
+
+#[derive(Serialize)]
+struct Score(u32);
+
+#[derive(Serialize, Deserialize)]
+struct SpawnAt {
+    x: f32,
+    y: f32,
+}
+
+impl Command for SpawnAt {
+    fn apply(self, world: &mut World) -> Result<()> {
+        let heading = world.get_resource_mut::<Rng>().unwrap().range(0.0, std::f32::consts::TAU);
+        world
+            .create_entity()
+            .with_component(Position { x: self.x, y: self.y })?
+            .with_component(Heading(heading))?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Freeze;
+
+impl Command for Freeze {
+    fn apply(self, world: &mut World) -> Result<()> {
+        world.get_resource_mut::<Frozen>().unwrap().0 = true;
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Unregistered;
+
+impl Command for Unregistered {
+    fn apply(self, _: &mut World) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use eyre::Result;
+use jecs::entities::map_entities::{EntityMap, MapEntities};
+use jecs::entities::Entity;
+use jecs::replication::{Replicated, ReplicationClient, ReplicationServer, WorldDelta};
+use jecs::World;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+#[test]
+fn replicate_over_an_in_memory_channel() -> Result<()> {
+    let mut server_world = initialize_world();
+    server_world
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?
+        .with_component(Secret(42))?;
+    server_world.create_entity().with_component(Position(5.0, 5.0))?;
+    server_world.create_entity().with_component(Targets(Entity { id: 1, generation: 0 }))?;
+
+    let mut client_world = initialize_world();
+    client_world.create_entity().with_component(Position(-1.0, -1.0))?;
+
+    let mut server = ReplicationServer::new();
+    let client_id = server.add_client();
+    let mut client = ReplicationClient::new();
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+
+    server.capture(&server_world)?;
+    sender.send(server.delta_for(client_id)?.to_bytes()?)?;
+    client.apply(&mut client_world, &WorldDelta::from_bytes(&receiver.recv()?)?)?;
+    server.acknowledge(client_id, client.last_tick().unwrap());
+
+    let server_target = server_world.entity(1).unwrap();
+    let local_target = client.local_entity(server_target).unwrap();
+    assert_eq!(local_target.id, 2);
+    assert_eq!(positions(&client_world), vec![(0, -1.0), (1, 0.0), (2, 5.0)]);
+    assert!(client_world.query().with_component::<Secret>()?.run().0.is_empty());
+    let query = client_world.query().with_component::<Targets>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Targets>().unwrap().0, local_target);
+
+    server_world.set_component(Position(1.0, 0.0), 0)?;
+    server_world.delete_entity_by_id(1)?;
+    server.capture(&server_world)?;
+    let delta = server.delta_for(client_id)?;
+    assert_eq!(delta.baseline(), Some(1));
+    assert!(delta.spawned().is_empty());
+    assert_eq!(delta.despawned(), &[server_target]);
+
+    sender.send(delta.to_bytes()?)?;
+    client.apply(&mut client_world, &WorldDelta::from_bytes(&receiver.recv()?)?)?;
+    assert_eq!(positions(&client_world), vec![(0, -1.0), (1, 1.0)]);
+    assert!(client.local_entity(server_target).is_none());
+    Ok(())
+}
+
+#[test]
+fn full_resyncs_keep_the_client_entities() -> Result<()> {
+    let mut server_world = initialize_world();
+    server_world
+        .create_entity()
+        .with_component(Position(0.0, 0.0))?
+        .with_component(Secret(1))?
+        .with_component(Replicated)?;
+    server_world.create_entity().with_component(Position(5.0, 5.0))?;
+    server_world
+        .create_entity()
+        .with_component(Secret(2))?
+        .with_component(Replicated)?;
+    let mut server = ReplicationServer::new();
+    let first_client = server.add_client();
+    let mut client = ReplicationClient::new();
+    let mut client_world = initialize_world();
+
+    server.capture(&server_world)?;
+    let bytes = server.delta_for(first_client)?.to_bytes()?;
+    assert!(!String::from_utf8_lossy(&bytes).contains("::"));
+    client.apply(&mut client_world, &WorldDelta::from_bytes(&bytes)?)?;
+    let (first, second) = (server_world.entity(0).unwrap(), server_world.entity(1).unwrap());
+    let local = client.local_entity(first).unwrap();
+    let empty = client.local_entity(server_world.entity(2).unwrap()).unwrap();
+    assert!(client_world.is_alive(empty));
+
+    server_world.set_component(Position(3.0, 0.0), 0)?;
+    server_world.delete_entity_by_id(1)?;
+    server.capture(&server_world)?;
+    let second_client = server.add_client();
+    let resync = server.delta_for(second_client)?;
+    assert!(resync.baseline().is_none());
+    client.apply(&mut client_world, &resync)?;
+
+    assert_eq!(client.local_entity(first), Some(local));
+    assert!(client.local_entity(second).is_none());
+    assert!(client_world.is_alive(empty));
+    assert_eq!(positions(&client_world), vec![(local.id, 3.0)]);
+
+    // Losing its last replicated component doesn't despawn the local copy.
+    server.acknowledge(first_client, 2);
+    server_world.delete_component_by_entity_id::<Position>(0)?;
+    server.capture(&server_world)?;
+    client.apply(&mut client_world, &server.delta_for(first_client)?)?;
+    assert!(client_world.is_alive(local));
+    assert!(positions(&client_world).is_empty());
+    Ok(())
+}
+
+#[test]
+fn unchanged_worlds_produce_empty_deltas() -> Result<()> {
+    let mut server_world = initialize_world();
+    server_world.create_entity().with_component(Position(0.0, 0.0))?;
+    let mut server = ReplicationServer::new();
+    let client_id = server.add_client();
+    let mut client = ReplicationClient::new();
+    let mut client_world = initialize_world();
+
+    server.capture(&server_world)?;
+    client.apply(&mut client_world, &server.delta_for(client_id)?)?;
+    server.acknowledge(client_id, 1);
+    server.capture(&server_world)?;
+
+    let delta = server.delta_for(client_id)?;
+    assert!(delta.is_empty());
+    assert!(delta.to_bytes()?.len() < 32);
+
+    let unacknowledged = server.add_client();
+    assert_eq!(server.delta_for(unacknowledged)?.spawned().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn replicate_over_localhost_tcp() -> Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let address = listener.local_addr()?;
+
+    let server_thread = thread::spawn(move || -> Result<()> {
+        let (mut stream, _) = listener.accept()?;
+        let mut server_world = initialize_world();
+        let mut server = ReplicationServer::new();
+        let client_id = server.add_client();
+
+        for step in 0..3 {
+            server_world.create_entity().with_component(Position(step as f32, 0.0))?;
+            server.capture(&server_world)?;
+            server.delta_for(client_id)?.write_to(&mut stream)?;
+
+            let mut acknowledged = [0; 8];
+            stream.read_exact(&mut acknowledged)?;
+            server.acknowledge(client_id, u64::from_le_bytes(acknowledged));
+        }
+        Ok(())
+    });
+
+    let mut stream = TcpStream::connect(address)?;
+    let mut client_world = initialize_world();
+    let mut client = ReplicationClient::new();
+    for _ in 0..3 {
+        let delta = WorldDelta::read_from(&mut stream)?;
+        assert!(delta.spawned().len() == 1);
+        client.apply(&mut client_world, &delta)?;
+        stream.write_all(&client.last_tick().unwrap().to_le_bytes())?;
+    }
+
+    server_thread.join().unwrap()?;
+    assert_eq!(positions(&client_world), vec![(0, 0.0), (1, 1.0), (2, 2.0)]);
+    Ok(())
+}
+
+#[test]
+fn deltas_must_build_on_the_client_state() -> Result<()> {
+    let mut server_world = initialize_world();
+    server_world.create_entity().with_component(Position(0.0, 0.0))?;
+    let mut server = ReplicationServer::new();
+    let client_id = server.add_client();
+    server.capture(&server_world)?;
+    server.acknowledge(client_id, 1);
+    server.capture(&server_world)?;
+
+    let mut client = ReplicationClient::new();
+    let mut client_world = initialize_world();
+    assert!(client.apply(&mut client_world, &server.delta_for(client_id)?).is_err());
+    assert!(client.last_tick().is_none());
+    Ok(())
+}
+
+fn initialize_world() -> World {
+    let mut world = World::new();
+    world.register_replicated_component::<Position>();
+    world.register_replicated_component::<Targets>();
+    world.register_map_entities::<Targets>();
+    world.register_component::<Secret>();
+    world.register_component::<Replicated>();
+    world
+}
+
+fn positions(world: &World) -> Vec<(usize, f32)> {
+    let (indexes, components) = world.query().with_component::<Position>().unwrap().run();
+    let positions = indexes
+        .into_iter()
+        .zip(&components[0])
+        .map(|(index, position)| (index, position.borrow().downcast_ref::<Position>().unwrap().0))
+        .collect();
+    positions
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Position(f32, f32);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Targets(Entity);
+
+impl MapEntities for Targets {
+    fn map_entities(&mut self, map: &EntityMap) {
+        self.0 = map.map(self.0);
+    }
+}
+
+struct Secret(#[allow(dead_code)] u32);
@@ -0,0 +1,129 @@
+use eyre::Result;
+use jecs::World;
+
+#[test]
+fn restore_brings_the_world_back() -> Result<()> {
+    let mut world = initialize_world()?;
+    let snapshot = world.snapshot()?;
+    let before = positions(&world)?;
+
+    simulate(&mut world)?;
+    assert_ne!(positions(&world)?, before);
+
+    world.restore(&snapshot)?;
+    assert_eq!(positions(&world)?, before);
+    assert_eq!(world.get_resource::<Tick>().unwrap().0, 0);
+    assert!(world.entity(1).is_none());
+    assert_eq!(world.entity(2).unwrap().generation, 0);
+
+    Ok(())
+}
+
+#[test]
+fn resimulating_from_a_snapshot_is_deterministic() -> Result<()> {
+    let mut world = initialize_world()?;
+    let snapshot = world.snapshot()?;
+
+    simulate(&mut world)?;
+    let first_run = (positions(&world)?, world.entity(1), world.entity(3));
+
+    world.restore(&snapshot)?;
+    simulate(&mut world)?;
+    let second_run = (positions(&world)?, world.entity(1), world.entity(3));
+
+    assert_eq!(first_run, second_run);
+    Ok(())
+}
+
+#[test]
+fn snapshot_requires_cloneable_components() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.register_component::<Opaque>();
+    assert!(world.snapshot().is_ok());
+
+    world.add_component_by_entity_id(Opaque, 0)?;
+    assert!(world.snapshot().is_err());
+    Ok(())
+}
+
+#[test]
+fn components_registered_after_the_snapshot_survive_a_restore() -> Result<()> {
+    let mut world = initialize_world()?;
+    let snapshot = world.snapshot()?;
+
+    world.register_cloneable_component::<Velocity>();
+    world.add_component_by_entity_id(Velocity, 0)?;
+    world.restore(&snapshot)?;
+
+    world.add_component_by_entity_id(Velocity, 2)?;
+    let velocities = world.query().with_component::<Velocity>()?.run();
+    assert_eq!(velocities.0, vec![2]);
+    assert_eq!(positions(&world)?, vec![(0, 0, 0), (2, 2, 4)]);
+    Ok(())
+}
+
+#[test]
+fn snapshots_from_another_world_are_restored_by_component() -> Result<()> {
+    let mut world = initialize_world()?;
+
+    let mut other = World::new();
+    other.register_cloneable_component::<Velocity>();
+    other.register_cloneable_component::<Position>();
+    other.create_entity().with_component(Position(7, 7))?;
+    world.restore(&other.snapshot()?)?;
+    assert_eq!(positions(&world)?, vec![(0, 7, 7)]);
+
+    other.create_entity().with_component(Velocity)?;
+    let before = positions(&world)?;
+    assert!(world.restore(&other.snapshot()?).is_err());
+    assert_eq!(positions(&world)?, before);
+    Ok(())
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_cloneable_component::<Position>();
+    world.register_cloneable_resource::<Tick>();
+    world.add_resource(Tick(0));
+
+    for index in 0..3 {
+        world
+            .create_entity()
+            .with_component(Position(index, index * 2))?;
+    }
+    world.delete_entity_by_id(1)?;
+
+    Ok(world)
+}
+
+fn simulate(world: &mut World) -> Result<()> {
+    for _ in 0..3 {
+        world.get_resource_mut::<Tick>().unwrap().0 += 1;
+        world.query().for_each::<(Position,), _>(|_, (position,)| {
+            position.0 += 1;
+            position.1 -= 1;
+        })?;
+        world.create_entity().with_component(Position(0, 0))?;
+    }
+
+    Ok(())
+}
+
+fn positions(world: &World) -> Result<Vec<(usize, i32, i32)>> {
+    let mut positions = vec![];
+    world.query().for_each::<(Position,), _>(|index, (position,)| {
+        positions.push((index, position.0, position.1));
+    })?;
+    Ok(positions)
+}
+
+#[derive(Clone)]
+struct Position(pub i32, pub i32);
+
+#[derive(Clone)]
+struct Velocity;
+
+#[derive(Clone)]
+struct Tick(pub u32);
+
+struct Opaque;
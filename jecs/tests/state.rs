@@ -0,0 +1,91 @@
+use jecs::errors::Result;
+use jecs::state::{NextState, State};
+use jecs::{App, World};
+
+#[test]
+fn systems_only_run_in_their_state() -> Result<()> {
+    let mut app = App::new();
+    app.add_state(GameState::Running)
+        .insert_resource(Ticks(0))
+        .add_system_in_state(GameState::Running, count_tick)
+        .add_system_on_enter(GameState::Paused, |world: &mut World| {
+            world.get_resource_mut::<Ticks>().unwrap().0 += 100;
+            Ok(())
+        })
+        .add_system_on_exit(GameState::Paused, |world: &mut World| {
+            world.get_resource_mut::<Ticks>().unwrap().0 += 1000;
+            Ok(())
+        });
+
+    app.update()?;
+    assert_eq!(ticks(&app), 1);
+
+    request(&mut app, GameState::Paused);
+    app.update()?;
+    assert_eq!(current(&app), GameState::Paused);
+    assert_eq!(ticks(&app), 101);
+    app.update()?;
+    assert_eq!(ticks(&app), 101);
+
+    request(&mut app, GameState::Running);
+    app.update()?;
+    assert_eq!(current(&app), GameState::Running);
+    assert_eq!(ticks(&app), 1102);
+    Ok(())
+}
+
+#[test]
+fn transitions_requested_by_systems_apply_on_the_next_update() -> Result<()> {
+    let mut app = App::new();
+    app.add_state(GameState::Menu)
+        .add_system_in_state(GameState::Menu, start_game)
+        .add_system_on_enter(GameState::Menu, |world: &mut World| {
+            world.add_resource(Ticks(0));
+            Ok(())
+        });
+
+    app.update()?;
+    assert_eq!(current(&app), GameState::Menu);
+    assert_eq!(ticks(&app), 0);
+
+    app.update()?;
+    assert_eq!(current(&app), GameState::Running);
+    Ok(())
+}
+
+fn count_tick(world: &mut World) -> Result<()> {
+    world.get_resource_mut::<Ticks>().unwrap().0 += 1;
+    Ok(())
+}
+
+fn start_game(world: &mut World) -> Result<()> {
+    world
+        .get_resource_mut::<NextState<GameState>>()
+        .unwrap()
+        .set(GameState::Running);
+    Ok(())
+}
+
+fn request(app: &mut App, state: GameState) {
+    app.world_mut()
+        .get_resource_mut::<NextState<GameState>>()
+        .unwrap()
+        .set(state);
+}
+
+fn current(app: &App) -> GameState {
+    app.world().get_resource::<State<GameState>>().unwrap().get().clone()
+}
+
+fn ticks(app: &App) -> u32 {
+    app.world().get_resource::<Ticks>().unwrap().0
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum GameState {
+    Menu,
+    Running,
+    Paused,
+}
+
+struct Ticks(pub u32);
@@ -0,0 +1,71 @@
+#![cfg(feature = "sync")]
+
+use eyre::Result;
+use jecs::World;
+use std::rc::Rc;
+use std::thread;
+
+#[test]
+fn world_is_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<World>();
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn move_world_to_worker_thread() -> Result<()> {
+    let mut world = World::new();
+    world.register_component::<Location>();
+    world.add_resource(Gravity(9.8));
+    world.create_entity().with_component(Location(0.0, 0.0))?;
+
+    let world = thread::spawn(move || -> Result<World> {
+        let gravity = world.get_resource::<Gravity>().unwrap().0;
+        let query = world.query().with_component::<Location>()?.run();
+        let mut borrowed_location = query.1[0][0].borrow_mut();
+        borrowed_location.downcast_mut::<Location>().unwrap().1 -= gravity;
+        drop(borrowed_location);
+        Ok(world)
+    })
+    .join()
+    .unwrap()?;
+
+    let query = world.query().with_component::<Location>()?.run();
+    let borrowed_location = query.1[0][0].borrow();
+    let location = borrowed_location.downcast_ref::<Location>().unwrap();
+    assert_eq!(location.0, 0.0);
+    assert_eq!(location.1, -9.8);
+
+    Ok(())
+}
+
+#[test]
+fn non_send_resources_stay_on_their_thread() {
+    let mut world = World::new();
+    world.add_non_send_resource(Rc::new(Gravity(9.8)));
+    assert!(world.get_non_send_resource::<Rc<Gravity>>().is_some());
+
+    let result = thread::scope(|scope| {
+        scope
+            .spawn(|| world.get_non_send_resource::<Rc<Gravity>>().is_some())
+            .join()
+    });
+    assert!(result.is_err());
+
+    world.delete_non_send_resource::<Rc<Gravity>>();
+    let world = thread::spawn(move || world).join().unwrap();
+    assert!(world.get_non_send_resource::<Rc<Gravity>>().is_none());
+}
+
+#[test]
+fn worlds_dropped_on_other_threads_leak_non_send_resources() {
+    let gravity = Rc::new(Gravity(9.8));
+    let mut world = World::new();
+    world.add_non_send_resource(Rc::clone(&gravity));
+
+    thread::spawn(move || drop(world)).join().unwrap();
+    assert_eq!(Rc::strong_count(&gravity), 2);
+}
+
+struct Location(pub f32, pub f32);
+struct Gravity(pub f32);
@@ -0,0 +1,89 @@
+use jecs::errors::Result;
+use jecs::time::{FixedTime, ManualClock, Time, TimePlugin};
+use jecs::{App, World};
+use std::time::Duration;
+
+#[test]
+fn fixed_systems_run_at_a_fixed_rate_regardless_of_frame_rate() -> Result<()> {
+    let clock = ManualClock::new();
+    let mut app = App::new();
+    app.add_plugin(TimePlugin {
+        fixed_step: Duration::from_millis(10),
+        max_steps: 4,
+    })
+    .insert_resource(Time::with_clock(clock.clone()))
+    .insert_resource(Steps(0))
+    .add_fixed_system(count_step);
+
+    app.update()?;
+    assert_eq!(steps(&app), 0);
+
+    clock.advance(Duration::from_millis(35));
+    app.update()?;
+    assert_eq!(steps(&app), 3);
+
+    clock.advance(Duration::from_millis(5));
+    app.update()?;
+    assert_eq!(steps(&app), 4);
+
+    clock.advance(Duration::from_millis(4));
+    app.update()?;
+    assert_eq!(steps(&app), 4);
+
+    let fixed = app.world().get_resource::<FixedTime>().unwrap();
+    assert_eq!(fixed.ticks(), 4);
+    assert_eq!(fixed.accumulator(), Duration::from_millis(4));
+    Ok(())
+}
+
+#[test]
+fn catch_up_steps_are_capped() -> Result<()> {
+    let clock = ManualClock::new();
+    let mut app = App::new();
+    app.insert_resource(Time::with_clock(clock.clone()))
+        .insert_resource(FixedTime::new(Duration::from_millis(10)).with_max_steps(2))
+        .insert_resource(Steps(0))
+        .add_fixed_system(count_step);
+
+    app.update()?;
+    clock.advance(Duration::from_secs(1));
+    app.update()?;
+    assert_eq!(steps(&app), 2);
+
+    clock.advance(Duration::from_millis(10));
+    app.update()?;
+    assert_eq!(steps(&app), 3);
+    Ok(())
+}
+
+#[test]
+fn time_scale_slows_the_fixed_stage() -> Result<()> {
+    let clock = ManualClock::new();
+    let mut app = App::new();
+    app.insert_resource(Time::with_clock(clock.clone()))
+        .insert_resource(FixedTime::new(Duration::from_millis(10)))
+        .insert_resource(Steps(0))
+        .add_fixed_system(count_step);
+
+    app.update()?;
+    app.world_mut().get_resource_mut::<Time>().unwrap().set_time_scale(0.5);
+    clock.advance(Duration::from_millis(40));
+    app.update()?;
+
+    assert_eq!(steps(&app), 2);
+    let time = app.world().get_resource::<Time>().unwrap();
+    assert_eq!(time.elapsed(), Duration::from_millis(20));
+    assert_eq!(time.ticks(), 2);
+    Ok(())
+}
+
+fn count_step(world: &mut World) -> Result<()> {
+    world.get_resource_mut::<Steps>().unwrap().0 += 1;
+    Ok(())
+}
+
+fn steps(app: &App) -> u32 {
+    app.world().get_resource::<Steps>().unwrap().0
+}
+
+struct Steps(pub u32);
@@ -0,0 +1,111 @@
+use jecs::errors::Result;
+use jecs::entities::Entity;
+use jecs::events::Events;
+use jecs::time::{ManualClock, Time, TimePlugin};
+use jecs::timer::{Lifetime, LifetimeExpired, Timer, TimerFinished, TimerMode, TimerPlugin};
+use jecs::{App, World};
+use std::time::Duration;
+
+#[test]
+fn lifetimes_despawn_entities_when_they_run_out() -> Result<()> {
+    let (mut app, clock) = initialize_app();
+    app.world_mut()
+        .create_entity()
+        .with_component(Corpse)?
+        .with_component(Lifetime::from_seconds(10.0))?;
+    let corpse = app.world().entity(0).unwrap();
+
+    app.update()?;
+    clock.advance(Duration::from_secs(9));
+    app.update()?;
+    assert!(app.world().is_alive(corpse));
+    assert!(app.world_mut().drain_events::<LifetimeExpired>().is_empty());
+
+    clock.advance(Duration::from_secs(1));
+    app.update()?;
+    assert!(!app.world().is_alive(corpse));
+    assert_eq!(
+        app.world_mut().drain_events::<LifetimeExpired>(),
+        vec![LifetimeExpired { entity: corpse }]
+    );
+    Ok(())
+}
+
+#[test]
+fn finished_timers_send_events_and_run_their_action() -> Result<()> {
+    let (mut app, clock) = initialize_app();
+    app.world_mut()
+        .create_entity()
+        .with_component(Timer::from_seconds(5.0, TimerMode::Once).with_action(turn_into_zombie))?;
+    let bitten = app.world().entity(0).unwrap();
+
+    app.update()?;
+    clock.advance(Duration::from_secs(4));
+    app.update()?;
+    assert!(app.world().query().with_component::<Zombie>()?.run().0.is_empty());
+
+    clock.advance(Duration::from_secs(2));
+    app.update()?;
+    assert_eq!(app.world().query().with_component::<Zombie>()?.run().0, vec![0]);
+    assert_eq!(
+        app.world_mut().drain_events::<TimerFinished>(),
+        vec![TimerFinished { entity: bitten }]
+    );
+
+    clock.advance(Duration::from_secs(5));
+    app.update()?;
+    assert!(app.world_mut().drain_events::<TimerFinished>().is_empty());
+    Ok(())
+}
+
+#[test]
+fn undrained_timer_events_do_not_pile_up() -> Result<()> {
+    let (mut app, clock) = initialize_app();
+    for _ in 0..3 {
+        app.world_mut()
+            .create_entity()
+            .with_component(Timer::from_seconds(1.0, TimerMode::Repeating))?;
+    }
+
+    app.update()?;
+    for _ in 0..10 {
+        clock.advance(Duration::from_secs(1));
+        app.update()?;
+    }
+
+    // Only the events of the last update are still around for the next one.
+    assert_eq!(app.world().get_resource::<Events<TimerFinished>>().unwrap().len(), 3);
+    Ok(())
+}
+
+#[test]
+fn stalled_repeating_timers_fire_a_bounded_number_of_times() -> Result<()> {
+    let (mut app, clock) = initialize_app();
+    app.world_mut().create_entity().with_component(
+        Timer::new(Duration::from_nanos(1), TimerMode::Repeating).with_max_catch_up(4),
+    )?;
+
+    app.update()?;
+    clock.advance(Duration::from_secs(60));
+    app.update()?;
+    assert_eq!(app.world_mut().drain_events::<TimerFinished>().len(), 4);
+    Ok(())
+}
+
+fn initialize_app() -> (App, ManualClock) {
+    let clock = ManualClock::new();
+    let mut app = App::new();
+    app.add_plugin(TimePlugin::default())
+        .add_plugin(TimerPlugin)
+        .insert_resource(Time::with_clock(clock.clone()))
+        .register_component::<Corpse>()
+        .register_component::<Zombie>();
+    (app, clock)
+}
+
+fn turn_into_zombie(world: &mut World, entity: Entity) -> Result<()> {
+    world.add_component_by_entity_id(Zombie, entity.id)
+}
+
+struct Corpse;
+struct Zombie;
@@ -0,0 +1,98 @@
+use eyre::eyre;
+use jecs::errors::Result;
+use jecs::World;
+
+#[test]
+fn failed_transactions_leave_no_half_built_entities() -> Result<()> {
+    let mut world = initialize_world()?;
+
+    let result = world.transaction(|world| {
+        world
+            .create_entity()
+            .with_component(Health(100))?
+            .with_component(Unregistered)?;
+        Ok(())
+    });
+
+    assert!(result.is_err());
+    assert!(world.entity(1).is_none());
+    assert_eq!(world.query().with_component::<Health>()?.run().0, vec![0]);
+    Ok(())
+}
+
+#[test]
+fn failed_transactions_roll_back_every_change() -> Result<()> {
+    let mut world = initialize_world()?;
+    let zombie = world.entity(0).unwrap();
+
+    let result: Result<()> = world.transaction(|world| {
+        world.set_component(Health(1), 0)?;
+        world.add_component_by_entity_id(Speed, 0)?;
+        world.create_entity().with_component(Health(50))?;
+        world.delete_entity_by_id(0)?;
+        Err(eyre!("cancelled").into())
+    });
+
+    assert_eq!(result.err().unwrap().to_string(), "cancelled");
+    assert!(world.is_alive(zombie));
+    assert!(world.entity(1).is_none());
+    assert!(world.query().with_component::<Speed>()?.run().0.is_empty());
+    let query = world.query().with_component::<Health>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Health>().unwrap().0, 100);
+    Ok(())
+}
+
+#[test]
+fn nested_transactions_roll_back_independently() -> Result<()> {
+    let mut world = initialize_world()?;
+
+    let spawned = world.transaction(|world| {
+        world.create_entity().with_component(Health(50))?;
+        let inner: Result<()> = world.transaction(|world| {
+            world.create_entity().with_component(Health(10))?;
+            Err(eyre!("inner").into())
+        });
+        assert!(inner.is_err());
+        Ok(world.entity(1).unwrap())
+    })?;
+
+    assert!(world.is_alive(spawned));
+    assert!(world.entity(2).is_none());
+    Ok(())
+}
+
+#[test]
+fn committed_transactions_are_one_undo_step() -> Result<()> {
+    let mut world = initialize_world()?;
+    world.enable_journal();
+
+    world.transaction(|world| {
+        world.create_entity().with_component(Health(50))?;
+        world.set_component(Health(90), 0)
+    })?;
+    let result: Result<()> = world.transaction(|world| {
+        world.delete_entity_by_id(0)?;
+        Err(eyre!("cancelled").into())
+    });
+    assert!(result.is_err());
+
+    let journal = world.journal().unwrap();
+    assert_eq!((journal.undo_len(), journal.in_transaction()), (1, false));
+    world.undo();
+    assert!(world.entity(1).is_none());
+    let query = world.query().with_component::<Health>()?.run();
+    assert_eq!(query.1[0][0].borrow().downcast_ref::<Health>().unwrap().0, 100);
+    Ok(())
+}
+
+fn initialize_world() -> Result<World> {
+    let mut world = World::new();
+    world.register_component::<Health>();
+    world.register_component::<Speed>();
+    world.create_entity().with_component(Health(100))?;
+    Ok(world)
+}
+
+struct Health(pub u32);
+struct Speed;
+struct Unregistered;
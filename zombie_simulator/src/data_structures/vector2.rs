@@ -1,6 +1,7 @@
+use serde::Serialize;
 use std::ops::{Add, Sub};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct Vector2 {
 	pub x: f32,
 	pub y: f32
@@ -1,44 +1,92 @@
-use jecs::World;
+use jecs::App;
+use jecs::replay::Replay;
+use jecs::time::TimePlugin;
 use eyre::Result;
 use crate::resources::{
 	arena_size::ArenaSize,
 	background_color::BackgroundColor,
-	clicked_location::ClickedLocation,
-	entity_size::EntitySize,
+};
+use crate::plugins::{
+	arena::ArenaPlugin,
+	input::{Click, InputPlugin},
+	render::RenderPlugin,
+	state::{StartGame, StatePlugin, TogglePause},
 };
 use ggez::graphics::{self, Color};
-use crate::resources::entity_mesh::EntityMesh;
 use ggez::{Context, GameResult, GameError};
-use ggez::event::EventHandler;
+use ggez::event::{self, EventHandler, KeyCode, KeyMods, MouseButton};
+use std::path::PathBuf;
 
 pub mod data_structures;
+pub mod plugins;
 pub mod resources;
 
 #[derive(Debug)]
 pub struct MainState {
-	world: World
+	app: App,
+	replay_path: Option<PathBuf>
 }
 
 impl MainState {
 	pub fn new(arena_size: ArenaSize, background_color: Color, entity_size: f32, ctx: &mut Context) -> Result<Self> {
-		let mut world = World::new();
-		world.add_resource(arena_size);
-		world.add_resource(BackgroundColor(background_color));
-		world.add_resource(ClickedLocation::new());
-		world.add_resource(EntitySize::new(entity_size));
-		world.add_resource(EntityMesh::new(entity_size, ctx)?);
-		Ok(Self { world })
+		let mut app = App::new();
+		app.add_plugin(TimePlugin::default())
+			.add_plugin(StatePlugin)
+			.add_plugin(ArenaPlugin { arena_size, background_color })
+			.add_plugin(InputPlugin)
+			.add_plugin(RenderPlugin::new(entity_size, ctx)?);
+		Ok(Self { app, replay_path: None })
+	}
+
+	// Records the run and saves it to `path` when the window closes.
+	pub fn record(&mut self, seed: u64, path: impl Into<PathBuf>) {
+		self.app.start_recording(seed);
+		self.replay_path = Some(path.into());
+	}
+
+	pub fn play(&mut self, path: impl Into<PathBuf>) -> Result<()> {
+		self.app.play(Replay::load(path.into())?);
+		Ok(())
 	}
 }
 
 impl EventHandler<GameError> for MainState {
 	fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
-		Ok(())
+		self.app.update().map_err(|error| GameError::CustomError(error.to_string()))
 	}
 
 	fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-		let bg_color = self.world.get_resource::<BackgroundColor>().expect("Could not find background color");
+		let bg_color = self.app.world().get_resource::<BackgroundColor>().expect("Could not find background color");
 		graphics::clear(ctx, **bg_color);
 		graphics::present(ctx)
 	}
+
+	fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+		if button == MouseButton::Left {
+			self.app.send_command(Click { x, y }).expect("Click command is not registered");
+		}
+	}
+
+	// Enter starts the game from the menu, P or space pauses it and escape
+	// still quits.
+	fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods, repeat: bool) {
+		if repeat {
+			return;
+		}
+		match keycode {
+			KeyCode::Return => self.app.send_command(StartGame).expect("StartGame command is not registered"),
+			KeyCode::P | KeyCode::Space => self.app.send_command(TogglePause).expect("TogglePause command is not registered"),
+			KeyCode::Escape => event::quit(ctx),
+			_ => {}
+		}
+	}
+
+	fn quit_event(&mut self, _ctx: &mut Context) -> bool {
+		if let (Some(replay), Some(path)) = (self.app.stop_recording(), &self.replay_path) {
+			if let Err(error) = replay.save(path) {
+				eprintln!("Could not save the replay to {}: {}", path.display(), error);
+			}
+		}
+		false
+	}
 }
\ No newline at end of file
@@ -2,9 +2,11 @@ use zombie_simulator::MainState;
 use ggez::graphics::Color;
 use ggez::{ContextBuilder};
 use ggez::event::{self};
-use eyre::Result;
+use eyre::{eyre, Result};
 use ggez::conf::WindowMode;
 use zombie_simulator::resources::arena_size::ArenaSize;
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 fn main() -> Result<()> {
     let window_mode = WindowMode::default().dimensions(1024.0, 1024.0);
@@ -17,7 +19,27 @@ fn main() -> Result<()> {
     let background_color = Color::from_rgb(29, 43, 83);
     let entity_size = 15.0;
 
-    let main_state = MainState::new(arena_size, background_color, entity_size, &mut ctx)?;
+    let mut main_state = MainState::new(arena_size, background_color, entity_size, &mut ctx)?;
+
+    // `--record <file>` saves the run for bug reports when the window closes,
+    // `--replay <file>` plays such a run back.
+    let args: Vec<String> = env::args().collect();
+    if let Some(path) = flag_value(&args, "--replay")? {
+        main_state.play(path)?;
+    } else if let Some(path) = flag_value(&args, "--record")? {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        main_state.record(seed, path);
+    }
 
     event::run(ctx, event_loop, main_state);
+}
+
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Result<Option<&'a str>> {
+    match args.iter().position(|arg| arg == flag) {
+        Some(position) => match args.get(position + 1) {
+            Some(value) => Ok(Some(value.as_str())),
+            None => Err(eyre!("{} needs a file path", flag)),
+        },
+        None => Ok(None),
+    }
 }
\ No newline at end of file
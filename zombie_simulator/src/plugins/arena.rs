@@ -0,0 +1,15 @@
+use crate::resources::{arena_size::ArenaSize, background_color::BackgroundColor};
+use ggez::graphics::Color;
+use jecs::{App, Plugin};
+
+pub struct ArenaPlugin {
+	pub arena_size: ArenaSize,
+	pub background_color: Color
+}
+
+impl Plugin for ArenaPlugin {
+	fn build(self, app: &mut App) {
+		app.insert_resource(self.arena_size)
+			.insert_resource(BackgroundColor(self.background_color));
+	}
+}
@@ -0,0 +1,34 @@
+use crate::data_structures::vector2::Vector2;
+use crate::resources::clicked_location::ClickedLocation;
+use jecs::errors::Result;
+use jecs::replay::Command;
+use jecs::{App, Plugin, World};
+use serde::{Deserialize, Serialize};
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+	fn build(self, app: &mut App) {
+		app.insert_resource(ClickedLocation::new())
+			.register_command::<Click>();
+		// Clicks only change this resource, so replays have to hash it to
+		// notice when they go their own way.
+		app.world_mut().register_hashed_resource::<ClickedLocation>();
+	}
+}
+
+// Mouse clicks go through the command queue so they end up in replays.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Click {
+	pub x: f32,
+	pub y: f32
+}
+
+impl Command for Click {
+	fn apply(self, world: &mut World) -> Result<()> {
+		if let Some(clicked_location) = world.get_resource_mut::<ClickedLocation>() {
+			clicked_location.location = Some(Vector2::new(self.x, self.y));
+		}
+		Ok(())
+	}
+}
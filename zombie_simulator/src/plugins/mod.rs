@@ -0,0 +1,4 @@
+pub mod arena;
+pub mod input;
+pub mod render;
+pub mod state;
\ No newline at end of file
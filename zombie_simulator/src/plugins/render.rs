@@ -0,0 +1,23 @@
+use crate::resources::{entity_mesh::EntityMesh, entity_size::EntitySize};
+use eyre::Result;
+use ggez::Context;
+use jecs::{App, Plugin};
+
+pub struct RenderPlugin {
+	entity_size: f32,
+	entity_mesh: EntityMesh
+}
+
+impl RenderPlugin {
+	pub fn new(entity_size: f32, ctx: &mut Context) -> Result<Self> {
+		let entity_mesh = EntityMesh::new(entity_size, ctx)?;
+		Ok(Self { entity_size, entity_mesh })
+	}
+}
+
+impl Plugin for RenderPlugin {
+	fn build(self, app: &mut App) {
+		app.insert_resource(EntitySize::new(self.entity_size))
+			.insert_resource(self.entity_mesh);
+	}
+}
@@ -0,0 +1,83 @@
+use crate::resources::clicked_location::ClickedLocation;
+use jecs::errors::Result;
+use jecs::replay::Command;
+use jecs::state::{NextState, State};
+use jecs::{App, Plugin, World};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AppState {
+	Menu,
+	Running,
+	Paused
+}
+
+pub struct StatePlugin;
+
+impl Plugin for StatePlugin {
+	fn build(self, app: &mut App) {
+		app.add_state(AppState::Menu)
+			.register_command::<StartGame>()
+			.register_command::<TogglePause>()
+			.add_system_in_state(AppState::Menu, start_on_click)
+			.add_system_on_exit(AppState::Paused, forget_click);
+	}
+}
+
+// Keys go through the command queue like clicks, so replays start and pause
+// where the recording did.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StartGame;
+
+impl Command for StartGame {
+	fn apply(self, world: &mut World) -> Result<()> {
+		transition(world, |state| match state {
+			AppState::Menu => Some(AppState::Running),
+			_ => None
+		});
+		Ok(())
+	}
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TogglePause;
+
+impl Command for TogglePause {
+	fn apply(self, world: &mut World) -> Result<()> {
+		transition(world, |state| match state {
+			AppState::Running => Some(AppState::Paused),
+			AppState::Paused => Some(AppState::Running),
+			AppState::Menu => None
+		});
+		Ok(())
+	}
+}
+
+fn transition(world: &mut World, next: impl FnOnce(&AppState) -> Option<AppState>) {
+	let next = match world.get_resource::<State<AppState>>().and_then(|state| next(state.get())) {
+		Some(next) => next,
+		None => return
+	};
+	if let Some(next_state) = world.get_resource_mut::<NextState<AppState>>() {
+		next_state.set(next);
+	}
+}
+
+// Clicking anywhere on the menu starts the game.
+fn start_on_click(world: &mut World) -> Result<()> {
+	let clicked = world
+		.get_resource_mut::<ClickedLocation>()
+		.and_then(|clicked_location| clicked_location.location.take());
+	if clicked.is_some() {
+		StartGame.apply(world)?;
+	}
+	Ok(())
+}
+
+// Clicks made while paused don't carry over into the game.
+fn forget_click(world: &mut World) -> Result<()> {
+	if let Some(clicked_location) = world.get_resource_mut::<ClickedLocation>() {
+		clicked_location.location = None;
+	}
+	Ok(())
+}
@@ -1,6 +1,7 @@
 use crate::data_structures::vector2::Vector2;
+use serde::Serialize;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize)]
 pub struct ClickedLocation {
 	pub location: Option<Vector2>
 }